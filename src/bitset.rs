@@ -0,0 +1,302 @@
+use std::fmt::Debug;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Bits set in a run of words, lowest first
+fn ones(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(w, word)| {
+        let mut rest = *word;
+        std::iter::from_fn(move || {
+            (rest != 0).then(|| {
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                w * WORD_BITS + bit
+            })
+        })
+    })
+}
+
+/// Every subset of the given elements, starting with the empty set. Elements
+/// are picked by the bits of a counter, so there can't be 64 or more of them
+fn subsets_of<S>(elements: Vec<usize>) -> impl Iterator<Item = S>
+where
+    S: FromIterator<usize>,
+{
+    assert!(elements.len() < WORD_BITS, "Too many subsets to enumerate");
+
+    (0_u64..(1 << elements.len()))
+        .map(move |picks| ones(&[picks]).map(|i| elements[i]).collect::<S>())
+}
+
+/// Bitset with room for `WORDS * 64` elements that lives on the stack and is
+/// `Copy`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedBitSet<const WORDS: usize> {
+    words: [u64; WORDS],
+}
+
+/// Handy for anything with up to 64 elements
+pub type BitSet64 = FixedBitSet<1>;
+
+impl<const WORDS: usize> Default for FixedBitSet<WORDS> {
+    fn default() -> Self {
+        Self { words: [0; WORDS] }
+    }
+}
+
+impl<const WORDS: usize> FixedBitSet<WORDS> {
+    pub const CAPACITY: usize = WORDS * WORD_BITS;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_words(words: [u64; WORDS]) -> Self {
+        Self { words }
+    }
+
+    /// Add an element, returning true if it wasn't already there
+    pub fn insert(&mut self, i: usize) -> bool {
+        assert!(i < Self::CAPACITY, "{i} doesn't fit in the bitset");
+        let added = !self.contains(i);
+        self.words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+        added
+    }
+
+    /// Take an element out, returning true if it was there
+    pub fn remove(&mut self, i: usize) -> bool {
+        let removed = self.contains(i);
+        if removed {
+            self.words[i / WORD_BITS] &= !(1 << (i % WORD_BITS));
+        }
+        removed
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < Self::CAPACITY
+            && self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Smallest element
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_words(std::array::from_fn(|i| {
+            self.words[i] | other.words[i]
+        }))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_words(std::array::from_fn(|i| {
+            self.words[i] & other.words[i]
+        }))
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        Self::from_words(std::array::from_fn(|i| {
+            self.words[i] & !other.words[i]
+        }))
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// Elements in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+
+    /// Every subset of this set, starting with the empty set
+    pub fn subsets(&self) -> impl Iterator<Item = Self> {
+        subsets_of(self.iter().collect())
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for FixedBitSet<WORDS> {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut result = Self::new();
+        iter.into_iter().for_each(|i| {
+            result.insert(i);
+        });
+        result
+    }
+}
+
+impl<const WORDS: usize> Debug for FixedBitSet<WORDS> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Bitset that grows to fit whatever gets put in it. Trailing empty words are
+/// always trimmed off, so equal sets have equal words
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(bits.div_ceil(WORD_BITS)),
+        }
+    }
+
+    fn trim(mut self) -> Self {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+        self
+    }
+
+    fn word(&self, w: usize) -> u64 {
+        self.words.get(w).copied().unwrap_or(0)
+    }
+
+    /// Combine this set with another one word by word
+    fn zip_words<F>(&self, other: &BitSet, op: F) -> BitSet
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        let len = self.words.len().max(other.words.len());
+        let words = (0..len).map(|w| op(self.word(w), other.word(w))).collect();
+
+        BitSet { words }.trim()
+    }
+
+    /// Add an element, returning true if it wasn't already there
+    pub fn insert(&mut self, i: usize) -> bool {
+        let w = i / WORD_BITS;
+        if w >= self.words.len() {
+            self.words.resize(w + 1, 0);
+        }
+
+        let added = !self.contains(i);
+        self.words[w] |= 1 << (i % WORD_BITS);
+        added
+    }
+
+    /// Take an element out, returning true if it was there
+    pub fn remove(&mut self, i: usize) -> bool {
+        let removed = self.contains(i);
+        if removed {
+            self.words[i / WORD_BITS] &= !(1 << (i % WORD_BITS));
+            *self = std::mem::take(self).trim();
+        }
+        removed
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.word(i / WORD_BITS) & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Smallest element
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        self.zip_words(other, |a, b| a & !b)
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_disjoint(&self, other: &BitSet) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// Elements in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+
+    /// Every subset of this set, starting with the empty set
+    pub fn subsets(&self) -> impl Iterator<Item = BitSet> {
+        subsets_of(self.iter().collect())
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut result = Self::new();
+        iter.into_iter().for_each(|i| {
+            result.insert(i);
+        });
+        result
+    }
+}
+
+impl Debug for BitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn test_fixed_bitset() {
+    let a = FixedBitSet::<2>::from_iter([1, 5, 64, 100]);
+    let b = FixedBitSet::<2>::from_iter([5, 100, 127]);
+
+    assert_eq!(a.len(), 4);
+    assert!(a.contains(64) && !a.contains(63) && !a.contains(500));
+    assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), vec![5, 100]);
+    assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![1, 64]);
+    assert_eq!(a.union(&b).len(), 5);
+    assert_eq!(b.first(), Some(5));
+    assert!(a.intersection(&b).is_subset(&b));
+}
+
+#[test]
+fn test_growable_bitset() {
+    let mut a = BitSet::from_iter([3, 200]);
+    let b = BitSet::from_iter([3]);
+
+    assert_eq!(a.difference(&b), BitSet::from_iter([200]));
+    assert!(a.remove(200));
+    assert_eq!(a, b);
+    assert!(BitSet::from_iter([70]).is_disjoint(&b));
+
+    let subsets = BitSet::from_iter([2, 9, 130]).subsets().collect::<Vec<_>>();
+    assert_eq!(subsets.len(), 8);
+    assert_eq!(subsets[0], BitSet::new());
+    assert!(subsets.contains(&BitSet::from_iter([2, 130])));
+}
@@ -1,31 +1,306 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
 use iter_tools::Itertools;
 
-/// Frivolous structure for drawing and debugging
-pub struct Canvas {
+/// Where the pixels of a canvas actually live. Anything never written reads as
+/// the empty char
+pub trait Storage {
+    fn new(empty_char: char) -> Self;
+
+    fn get(&self, point: (i32, i32)) -> char;
+
+    fn set(&mut self, point: (i32, i32), v: char);
+
+    /// Hint that everything between `min` and `max` (inclusive) is about to be
+    /// drawn on
+    fn reserve(&mut self, min: (i32, i32), max: (i32, i32)) {}
+}
+
+/// Flat buffer of chars that over-allocates whenever it has to grow, so that
+/// drawing outward one point at a time doesn't copy the whole image each time
+#[derive(Debug)]
+pub struct Dense {
+    empty_char: char,
+    cells: Vec<char>,
+    top_left: (i32, i32),
+    width: i32,
+    height: i32,
+}
+
+impl Dense {
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        let (dx, dy) = (x - self.top_left.0, y - self.top_left.1);
+
+        (dx >= 0 && dy >= 0 && dx < self.width && dy < self.height)
+            .then(|| (dy * self.width + dx) as usize)
+    }
+}
+
+impl Storage for Dense {
+    fn new(empty_char: char) -> Self {
+        Self {
+            empty_char,
+            cells: vec![],
+            top_left: (0, 0),
+            width: 0,
+            height: 0,
+        }
+    }
+
+    fn get(&self, point: (i32, i32)) -> char {
+        self.index(point)
+            .map(|i| self.cells[i])
+            .unwrap_or(self.empty_char)
+    }
+
+    fn set(&mut self, point: (i32, i32), v: char) {
+        self.reserve(point, point);
+
+        let i = self.index(point).expect("Reserved just above");
+        self.cells[i] = v;
+    }
+
+    fn reserve(&mut self, min: (i32, i32), max: (i32, i32)) {
+        if self.index(min).is_some() && self.index(max).is_some() {
+            return;
+        }
+
+        let (left, top) = self.top_left;
+        let (right, bottom) = (left + self.width, top + self.height);
+
+        // Any side that needs to move grows by at least the current size of
+        // the buffer in that dimension, which keeps growth amortized
+        let grow = |needed: i32, current: i32| {
+            if needed > 0 {
+                needed.max(current)
+            } else {
+                0
+            }
+        };
+
+        let (new_left, new_top, new_right, new_bottom) =
+            if self.cells.is_empty() {
+                (min.0, min.1, max.0 + 1, max.1 + 1)
+            } else {
+                (
+                    left - grow(left - min.0, self.width),
+                    top - grow(top - min.1, self.height),
+                    right + grow(max.0 + 1 - right, self.width),
+                    bottom + grow(max.1 + 1 - bottom, self.height),
+                )
+            };
+
+        let mut grown = Dense {
+            empty_char: self.empty_char,
+            top_left: (new_left, new_top),
+            width: new_right - new_left,
+            height: new_bottom - new_top,
+            cells: vec![],
+        };
+
+        grown.cells =
+            vec![self.empty_char; (grown.width * grown.height) as usize];
+
+        // Copy the old buffer over a row at a time
+        if self.width > 0 {
+            self.cells.chunks(self.width as usize).enumerate().for_each(
+                |(row, chunk)| {
+                    let start = grown
+                        .index((left, top + row as i32))
+                        .expect("The new buffer covers the old one");
+                    grown.cells[start..(start + chunk.len())]
+                        .copy_from_slice(chunk);
+                },
+            );
+        }
+
+        *self = grown;
+    }
+}
+
+/// Hash map of only the points that have been drawn on. Better for huge and
+/// mostly empty spaces
+#[derive(Debug)]
+pub struct Sparse {
+    empty_char: char,
+    cells: HashMap<(i32, i32), char>,
+}
+
+impl Storage for Sparse {
+    fn new(empty_char: char) -> Self {
+        Self {
+            empty_char,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn get(&self, point: (i32, i32)) -> char {
+        self.cells.get(&point).copied().unwrap_or(self.empty_char)
+    }
+
+    fn set(&mut self, point: (i32, i32), v: char) {
+        if v == self.empty_char {
+            self.cells.remove(&point);
+        } else {
+            self.cells.insert(point, v);
+        }
+    }
+}
+
+/// Name of the layer every canvas starts with
+pub const BASE_LAYER: &str = "base";
+
+/// One sheet of the canvas. Empty points let the layers underneath show
+/// through
+#[derive(Debug)]
+struct Layer<S> {
+    name: String,
+    z: i32,
+    visible: bool,
+    storage: S,
+
+    /// Rightmost point drawn in each row, even with the empty char, so rows
+    /// print as wide as they were drawn
+    row_ends: HashMap<i32, i32>,
+}
+
+impl<S> Layer<S>
+where
+    S: Storage,
+{
+    fn new(name: &str, z: i32, empty_char: char) -> Self {
+        Self {
+            name: name.to_owned(),
+            z,
+            visible: true,
+            storage: S::new(empty_char),
+            row_ends: HashMap::new(),
+        }
+    }
+
+    fn set(&mut self, (x, y): (i32, i32), v: char) {
+        self.storage.set((x, y), v);
+
+        let end = self.row_ends.entry(y).or_insert(x);
+        *end = x.max(*end);
+    }
+}
+
+/// Frivolous structure for drawing and debugging. The `top_left`, `width`, and
+/// `height` describe the smallest box around everything drawn so far
+#[derive(Debug)]
+pub struct Canvas<S = Dense> {
     pub empty_char: char,
-    pub contents: Vec<char>,
     pub width: i32,
     pub height: i32,
     pub top_left: (i32, i32),
     pub draw_enabled: bool,
     pub render_enabled: bool,
+
+    /// Sorted from bottom to top
+    layers: Vec<Layer<S>>,
+
+    /// Index of the layer being drawn on
+    active: usize,
 }
 
 impl Canvas {
     pub fn new(empty_char: char, center: (i32, i32)) -> Self {
+        Self::with_storage(empty_char, center)
+    }
+
+    /// Load a picture of a map with its top left corner at the given point.
+    /// Spaces are treated as empty
+    pub fn from_lines<I, L>(origin: (i32, i32), lines: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<str>,
+    {
+        let mut result = Self::new(' ', origin);
+        result.draw_text(origin, lines);
+        result
+    }
+}
+
+impl Canvas<Sparse> {
+    pub fn sparse(empty_char: char, center: (i32, i32)) -> Self {
+        Self::with_storage(empty_char, center)
+    }
+}
+
+impl<S> Canvas<S>
+where
+    S: Storage,
+{
+    pub fn with_storage(empty_char: char, center: (i32, i32)) -> Self {
         Self {
             empty_char,
-            contents: vec![empty_char],
             width: 1,
             height: 1,
             top_left: center,
             draw_enabled: true,
             render_enabled: false,
+            layers: vec![Layer::new(BASE_LAYER, 0, empty_char)],
+            active: 0,
         }
     }
 
-    /// Calculate the index in the image buffer that corresponds to the given
-    /// point
+    fn layer_index(&self, name: &str) -> usize {
+        self.layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .unwrap_or_else(|| panic!("No layer named {name}"))
+    }
+
+    /// Add a layer drawn above every layer with a lower `z` (and above layers
+    /// with the same `z` that were added before it). Does nothing if a layer
+    /// with this name already exists
+    pub fn add_layer(&mut self, name: &str, z: i32) {
+        if self.layers.iter().any(|layer| layer.name == name) {
+            return;
+        }
+
+        let i = self.layers.partition_point(|layer| layer.z <= z);
+
+        self.layers.insert(i, Layer::new(name, z, self.empty_char));
+
+        if i <= self.active {
+            self.active += 1;
+        }
+    }
+
+    /// Send all drawing from here on to the named layer
+    pub fn select_layer(&mut self, name: &str) {
+        self.active = self.layer_index(name);
+    }
+
+    /// Erase everything drawn on the named layer
+    pub fn clear_layer(&mut self, name: &str) {
+        let i = self.layer_index(name);
+        self.layers[i].storage = S::new(self.empty_char);
+        self.layers[i].row_ends.clear();
+    }
+
+    pub fn set_layer_visible(&mut self, name: &str, visible: bool) {
+        let i = self.layer_index(name);
+        self.layers[i].visible = visible;
+    }
+
+    pub fn toggle_layer(&mut self, name: &str) {
+        let i = self.layer_index(name);
+        self.layers[i].visible = !self.layers[i].visible;
+    }
+
+    /// Get the char at the given point on only the named layer
+    pub fn get_on_layer(&self, name: &str, point: (i32, i32)) -> char {
+        self.layers[self.layer_index(name)].storage.get(point)
+    }
+
+    /// Calculate the index into the drawn area that corresponds to the given
+    /// point (row-major)
     pub fn index_for_coordinate(&self, coord: (i32, i32)) -> usize {
         let row_start = ((coord.1 - self.top_left.1) * self.width) as usize;
         let distance_into_row = (coord.0 - self.top_left.0) as usize;
@@ -34,7 +309,7 @@ impl Canvas {
     }
 
     /// Get the coordinates in viewed space of the point at the given index in
-    /// the image buffer
+    /// the drawn area
     pub fn coordinate_from_index(&self, i: usize) -> (i32, i32) {
         (
             i as i32 % self.width + self.top_left.0,
@@ -42,66 +317,50 @@ impl Canvas {
         )
     }
 
-    /// Get the coordinates of the given buffer index in canvas space
+    /// Get the coordinates of the given index in canvas space
     pub fn canvas_coord_from_index(&self, i: usize) -> (i32, i32) {
         (i as i32 % self.width, i as i32 / self.width)
     }
 
-    /// Resize the canvas if needed to allow the new point to be displayed
-    fn resize_if_needed(&mut self, new_points: &[&(i32, i32)]) {
-        let x_bound = (self.top_left.0)..(self.top_left.0 + self.width);
-        let y_bound = (self.top_left.1)..(self.top_left.1 + self.height);
+    /// Number of points in the drawn area
+    pub fn len(&self) -> usize {
+        (self.width * self.height) as usize
+    }
 
-        // No resize needed
-        if new_points.iter().fold(true, |in_bounds, (x, y)| {
-            in_bounds && x_bound.contains(x) && y_bound.contains(y)
-        }) {
-            return;
-        }
+    /// Get the char at the given point as seen from above all the visible
+    /// layers
+    pub fn get(&self, point: (i32, i32)) -> char {
+        self.layers
+            .iter()
+            .rev()
+            .filter(|layer| layer.visible)
+            .map(|layer| layer.storage.get(point))
+            .find(|c| *c != self.empty_char)
+            .unwrap_or(self.empty_char)
+    }
 
-        // Determine the new viewport
-        let new_top_left = new_points.iter().fold(
-            self.top_left,
-            |(old_min_x, old_min_y), (x, y)| {
-                (old_min_x.min(*x), old_min_y.min(*y))
-            },
+    /// Grow the drawn area if needed to include the new points
+    fn resize_if_needed(&mut self, new_points: &[&(i32, i32)]) {
+        let bottom_right = (
+            self.top_left.0 + self.width - 1,
+            self.top_left.1 + self.height - 1,
         );
 
-        let top_left_shift = (
-            new_top_left.0 - self.top_left.0,
-            new_top_left.1 - self.top_left.1,
+        let (min, max) = new_points.iter().fold(
+            (self.top_left, bottom_right),
+            |(min, max), (x, y)| {
+                (
+                    (min.0.min(*x), min.1.min(*y)),
+                    (max.0.max(*x), max.1.max(*y)),
+                )
+            },
         );
 
-        let new_width = new_points
-            .iter()
-            .fold(self.width - top_left_shift.0, |new_width, (x, _)| {
-                new_width.max(x - new_top_left.0 + 1)
-            });
-
-        let new_height = new_points
-            .iter()
-            .fold(self.height - top_left_shift.1, |new_height, (_, y)| {
-                new_height.max(y - new_top_left.1 + 1)
-            });
-
-        let mut new_canvas = Canvas {
-            empty_char: self.empty_char,
-            top_left: new_top_left,
-            width: new_width,
-            height: new_height,
-            contents: vec![self.empty_char; (new_width * new_height) as usize],
-            render_enabled: self.render_enabled,
-            draw_enabled: self.draw_enabled,
-        };
-
-        // Add the old contents to the new contents
-        self.contents.iter().enumerate().for_each(|(i, c)| {
-            let coord = self.coordinate_from_index(i);
-            let new_i = new_canvas.index_for_coordinate(coord);
-            new_canvas.contents[new_i] = *c;
-        });
+        self.top_left = min;
+        self.width = max.0 - min.0 + 1;
+        self.height = max.1 - min.1 + 1;
 
-        *self = new_canvas;
+        self.layers[self.active].storage.reserve(min, max);
     }
 
     pub fn draw_point(&mut self, point: (i32, i32), v: char) {
@@ -110,9 +369,22 @@ impl Canvas {
         }
 
         self.resize_if_needed(&[&point]);
+        self.layers[self.active].set(point, v);
+    }
 
-        let index = self.index_for_coordinate(point);
-        self.contents[index] = v;
+    /// Draw each char of the given lines as a point, starting from the given
+    /// top left corner and going down a row for each line
+    pub fn draw_text<I, L>(&mut self, top_left: (i32, i32), lines: I)
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<str>,
+    {
+        lines.into_iter().zip(top_left.1..).for_each(|(line, y)| {
+            line.as_ref()
+                .chars()
+                .zip(top_left.0..)
+                .for_each(|(c, x)| self.draw_point((x, y), c));
+        });
     }
 
     pub fn draw_line(&mut self, start: (i32, i32), end: (i32, i32), p: char) {
@@ -123,27 +395,497 @@ impl Canvas {
         self.resize_if_needed(&[&start, &end]);
 
         let (delta, times) = match (start.0 == end.0, start.1 == end.1) {
-            (true, _) if start.1 < end.1 => (self.width, end.1 - start.1),
-            (true, _) => (-self.width, start.1 - end.1),
-            (_, true) if start.0 < end.0 => (1, end.0 - start.0),
-            (_, true) => (-1, start.0 - end.0),
+            (true, _) => ((0, (end.1 - start.1).signum()), end.1 - start.1),
+            (_, true) => (((end.0 - start.0).signum(), 0), end.0 - start.0),
             _ => unimplemented!("Horizontal or vertical lines only for now"),
         };
 
-        let index = self.index_for_coordinate(start) as i32;
+        (0..=times.abs()).for_each(|i| {
+            self.layers[self.active]
+                .set((start.0 + delta.0 * i, start.1 + delta.1 * i), p);
+        });
+    }
+
+    /// Fill every point in the rectangle between the two corners (inclusive)
+    pub fn fill_rect(
+        &mut self,
+        corner: (i32, i32),
+        other: (i32, i32),
+        v: char,
+    ) {
+        if !self.draw_enabled {
+            return;
+        }
+
+        let min = (corner.0.min(other.0), corner.1.min(other.1));
+        let max = (corner.0.max(other.0), corner.1.max(other.1));
 
-        (0..=times).map(i32::from).for_each(|i| {
-            self.contents[(index + delta * i) as usize] = p;
+        self.resize_if_needed(&[&min, &max]);
+
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                self.layers[self.active].set((x, y), v);
+            }
+        }
+    }
+
+    /// Fill every point within the given manhattan distance of the center
+    pub fn fill_diamond(&mut self, center: (i32, i32), radius: i32, v: char) {
+        if !self.draw_enabled {
+            return;
+        }
+
+        let (x, y) = center;
+        self.resize_if_needed(&[
+            &(x - radius, y - radius),
+            &(x + radius, y + radius),
+        ]);
+
+        for dy in -radius..=radius {
+            let reach = radius - dy.abs();
+            for dx in -reach..=reach {
+                self.layers[self.active].set((x + dx, y + dy), v);
+            }
+        }
+    }
+
+    /// Fill every point within the given (euclidean) distance of the center
+    pub fn fill_circle(&mut self, center: (i32, i32), radius: i32, v: char) {
+        if !self.draw_enabled {
+            return;
+        }
+
+        let (x, y) = center;
+        self.resize_if_needed(&[
+            &(x - radius, y - radius),
+            &(x + radius, y + radius),
+        ]);
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    self.layers[self.active].set((x + dx, y + dy), v);
+                }
+            }
+        }
+    }
+
+    /// Fill the region connected (horizontally and vertically) to the start
+    /// point without crossing any of the wall chars or leaving the drawn
+    /// area. Points already showing `v` are left alone. Returns the number of
+    /// points filled, which is still counted when drawing is disabled
+    pub fn flood_fill(
+        &mut self,
+        start: (i32, i32),
+        v: char,
+        walls: &[char],
+    ) -> usize {
+        let in_bounds = |(x, y): (i32, i32)| {
+            x >= self.top_left.0
+                && y >= self.top_left.1
+                && x < self.top_left.0 + self.width
+                && y < self.top_left.1 + self.height
+        };
+
+        let fillable = |p: (i32, i32)| {
+            let c = self.get(p);
+            in_bounds(p) && c != v && !walls.contains(&c)
+        };
+
+        let mut filled = HashSet::new();
+        let mut stack = vec![start];
+
+        while let Some(p @ (x, y)) = stack.pop() {
+            if filled.contains(&p) || !fillable(p) {
+                continue;
+            }
+
+            filled.insert(p);
+            stack.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+        }
+
+        if self.draw_enabled {
+            filled.iter().for_each(|p| {
+                self.layers[self.active].set(*p, v);
+            });
+        }
+
+        filled.len()
+    }
+
+    /// Viewport covering everything drawn so far at full resolution
+    pub fn full_view(&self) -> Viewport {
+        Viewport::new(self.top_left, self.width, self.height)
+    }
+
+    /// Squash the block of points starting at the given point into one char
+    fn sample(&self, (x, y): (i32, i32), view: &Viewport) -> char {
+        if view.zoom == 1 {
+            return self.get((x, y));
+        }
+
+        let block = (y..(y + view.zoom))
+            .flat_map(|y| (x..(x + view.zoom)).map(move |x| (x, y)))
+            .map(|p| self.get(p))
+            .collect_vec();
+
+        let priority_hit = match &view.downsample {
+            Downsample::Priority(order) => {
+                order.iter().copied().find(|c| block.contains(c))
+            }
+            Downsample::Majority => None,
+        };
+
+        // Majority vote, with ties going to whichever char showed up first
+        priority_hit.unwrap_or_else(|| {
+            block
+                .iter()
+                .copied()
+                .unique()
+                .map(|c| (c, block.iter().filter(|b| **b == c).count()))
+                .fold((self.empty_char, 0), |best, (c, count)| {
+                    if count > best.1 {
+                        (c, count)
+                    } else {
+                        best
+                    }
+                })
+                .0
+        })
+    }
+
+    /// Produce the lines of text showing the given viewport
+    pub fn view_lines(&self, view: &Viewport) -> Vec<String> {
+        let columns = (0..(view.width / view.zoom))
+            .map(|c| view.top_left.0 + c * view.zoom)
+            .collect_vec();
+        let rows = (0..(view.height / view.zoom))
+            .map(|r| view.top_left.1 + r * view.zoom)
+            .collect_vec();
+
+        let body = rows.iter().map(|y| {
+            columns
+                .iter()
+                .map(|x| self.sample((*x, *y), view))
+                .collect::<String>()
+        });
+
+        if !view.rulers {
+            return body.collect_vec();
+        }
+
+        let row_labels = rows.iter().map(|y| y.to_string()).collect_vec();
+        let label_width = row_labels.iter().map(String::len).max().unwrap_or(0);
+
+        // Columns get labeled at real world multiples of 5 (scaled by zoom),
+        // with the digits of the label stacked vertically
+        let tick = 5 * view.zoom;
+        let column_labels = columns
+            .iter()
+            .map(|x| {
+                (*x..(*x + view.zoom))
+                    .find(|m| m.rem_euclid(tick) == 0)
+                    .map(|m| m.to_string())
+                    .unwrap_or_default()
+            })
+            .collect_vec();
+        let ruler_height =
+            column_labels.iter().map(String::len).max().unwrap_or(0);
+
+        let ruler = (0..ruler_height).map(|r| {
+            let digits = column_labels
+                .iter()
+                .map(|label| {
+                    let pad = ruler_height - label.len();
+                    if r >= pad {
+                        label.as_bytes()[r - pad] as char
+                    } else {
+                        ' '
+                    }
+                })
+                .collect::<String>();
+
+            format!("{:label_width$} {digits}", "")
         });
+
+        ruler
+            .chain(
+                row_labels.iter().zip(body).map(|(label, line)| {
+                    format!("{label:>label_width$} {line}")
+                }),
+            )
+            .collect_vec()
     }
 
     pub fn render(&self) {
+        self.render_view(&self.full_view());
+    }
+
+    pub fn render_view(&self, view: &Viewport) {
         if self.render_enabled {
             println!();
-            self.contents
-                .chunks(self.width as usize)
-                .map(|chunk| chunk.iter().join(""))
+            self.view_lines(view)
+                .into_iter()
                 .for_each(|line| println!("{line}"));
         }
     }
 }
+
+/// Exact picture of everything drawn, one line per row. Each row goes as far
+/// as anything was drawn on it in a visible layer, so ragged pictures come
+/// back out the way they went in, trailing empty chars and all, and a blank
+/// canvas prints nothing
+impl<S> Display for Canvas<S>
+where
+    S: Storage,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = (self.top_left.1..(self.top_left.1 + self.height))
+            .map(|y| {
+                let end = self
+                    .layers
+                    .iter()
+                    .filter(|layer| layer.visible)
+                    .filter_map(|layer| layer.row_ends.get(&y))
+                    .max()
+                    .map_or(self.top_left.0 - 1, |end| *end);
+
+                (self.top_left.0..=end)
+                    .map(|x| self.get((x, y)))
+                    .collect::<String>()
+            })
+            .join("\n");
+
+        write!(f, "{}", rows.trim_end_matches('\n'))
+    }
+}
+
+/// How a zoomed out view decides what to show for a block of points
+#[derive(Debug, Clone)]
+pub enum Downsample {
+    /// Most common char in the block
+    Majority,
+
+    /// First char in the list that appears anywhere in the block, otherwise
+    /// the most common char
+    Priority(Vec<char>),
+}
+
+/// Rectangle of the canvas (in real world coordinates) to render, with
+/// optional zooming out and coordinate rulers
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    pub top_left: (i32, i32),
+    pub width: i32,
+    pub height: i32,
+    pub zoom: i32,
+    pub downsample: Downsample,
+    pub rulers: bool,
+}
+
+impl Viewport {
+    pub fn new(top_left: (i32, i32), width: i32, height: i32) -> Self {
+        Self {
+            top_left,
+            width,
+            height,
+            zoom: 1,
+            downsample: Downsample::Majority,
+            rulers: false,
+        }
+    }
+
+    /// Show each `zoom` x `zoom` block of points as a single char
+    pub fn zoomed_out(mut self, zoom: i32, downsample: Downsample) -> Self {
+        self.zoom = zoom.max(1);
+        self.downsample = downsample;
+        self
+    }
+
+    pub fn with_rulers(mut self) -> Self {
+        self.rulers = true;
+        self
+    }
+}
+
+#[test]
+fn test_dense_growth_keeps_contents() {
+    let mut canvas = Canvas::new('.', (0, 0));
+
+    // Spiral outward so every direction has to grow at some point
+    let points = (1..40)
+        .flat_map(|r| [(r, 0), (0, r), (-r, 0), (0, -r), (r, -r), (-r, r)])
+        .collect_vec();
+
+    points.iter().enumerate().for_each(|(i, p)| {
+        canvas.draw_point(*p, char::from(b'a' + (i % 26) as u8));
+    });
+
+    points.iter().enumerate().for_each(|(i, p)| {
+        assert_eq!(canvas.get(*p), char::from(b'a' + (i % 26) as u8));
+    });
+
+    assert_eq!(canvas.get((5, 5)), '.');
+    assert_eq!(canvas.top_left, (-39, -39));
+    assert_eq!((canvas.width, canvas.height), (79, 79));
+
+    // The buffer grows geometrically, so it's bigger than what's been drawn
+    assert!(canvas.layers[0].storage.cells.len() > canvas.len());
+}
+
+#[test]
+fn test_sparse_matches_dense() {
+    let mut dense = Canvas::new(' ', (0, 0));
+    let mut sparse = Canvas::sparse(' ', (0, 0));
+
+    for (start, end) in [(1, 1), (-3, 1), (-3, -5)].into_iter().tuple_windows()
+    {
+        dense.draw_line(start, end, '#');
+        sparse.draw_line(start, end, '#');
+    }
+
+    dense.draw_point((0, 0), 'o');
+    sparse.draw_point((0, 0), 'o');
+    sparse.draw_point((1_000_000, -1_000_000), '@');
+
+    for y in -5..=1 {
+        for x in -3..=1 {
+            assert_eq!(dense.get((x, y)), sparse.get((x, y)));
+        }
+    }
+
+    assert_eq!(sparse.get((1_000_000, -1_000_000)), '@');
+    assert_eq!(sparse.layers[0].storage.cells.len(), 13);
+}
+
+#[test]
+fn test_view_crop_with_rulers() {
+    let mut canvas = Canvas::new('.', (500, 0));
+    canvas.draw_line((498, 4), (498, 6), '#');
+    canvas.draw_line((498, 6), (496, 6), '#');
+    canvas.draw_point((500, 0), '+');
+
+    let view = Viewport::new((494, 0), 7, 3).with_rulers();
+
+    assert_eq!(
+        canvas.view_lines(&view),
+        vec![
+            "   4    5", //
+            "   9    0",
+            "   5    0",
+            "0 ......+",
+            "1 .......",
+            "2 .......",
+        ]
+    );
+}
+
+#[test]
+fn test_view_zoomed_out() {
+    let mut canvas = Canvas::new(' ', (0, 0));
+    canvas.draw_line((0, 0), (3, 0), '#');
+    canvas.draw_point((3, 3), 'o');
+
+    let majority =
+        Viewport::new((0, 0), 4, 4).zoomed_out(2, Downsample::Majority);
+    assert_eq!(canvas.view_lines(&majority), vec!["##", "  "]);
+
+    let priority = Viewport::new((0, 0), 4, 4)
+        .zoomed_out(2, Downsample::Priority(vec!['o']));
+    assert_eq!(canvas.view_lines(&priority), vec!["##", " o"]);
+}
+
+#[test]
+fn test_from_lines_round_trip() {
+    let picture = ["  ..#", "#.. .", " ## ."];
+    let canvas = Canvas::from_lines((-2, 10), picture);
+
+    assert_eq!(canvas.top_left, (-2, 10));
+    assert_eq!(canvas.get((2, 10)), '#');
+    assert_eq!(canvas.get((-2, 11)), '#');
+    assert_eq!(canvas.to_string(), picture.join("\n"));
+
+    // Ragged like the day 22 map, which isn't padded out to a rectangle
+    let ragged = ["        ...#", "        .#..", "...#.......#", "    ..#"];
+    let canvas = Canvas::from_lines((0, 0), ragged);
+    assert_eq!(canvas.to_string(), ragged.join("\n"));
+
+    // Trailing empty chars that were drawn stay put, whatever the empty char
+    let padded = ["#  ", " #", "   "];
+    let canvas = Canvas::from_lines((0, 0), padded);
+    assert_eq!(canvas.to_string(), padded.join("\n"));
+
+    let dotted = ["..#..", "#..", "."];
+    let mut canvas = Canvas::new('.', (0, 0));
+    canvas.draw_text((0, 0), dotted);
+    assert_eq!(canvas.to_string(), dotted.join("\n"));
+
+    assert_eq!(Canvas::new(' ', (3, 3)).to_string(), "");
+    assert_eq!(Canvas::from_lines((0, 0), [""; 0]).to_string(), "");
+}
+
+#[test]
+fn test_layers_overlay() {
+    let mut canvas = Canvas::from_lines((0, 0), ["abc", "def"]);
+
+    canvas.add_layer("path", 1);
+    canvas.add_layer("under", -1);
+    canvas.select_layer("path");
+    canvas.draw_line((0, 1), (2, 1), '*');
+    canvas.draw_point((1, 1), ' ');
+
+    assert_eq!(canvas.to_string(), "abc\n*e*");
+
+    canvas.toggle_layer("path");
+    assert_eq!(canvas.to_string(), "abc\ndef");
+
+    canvas.toggle_layer("path");
+    canvas.select_layer("under");
+    canvas.draw_point((1, 0), '!');
+    canvas.clear_layer(BASE_LAYER);
+    assert_eq!(canvas.to_string(), " !\n* *");
+    assert_eq!(canvas.get_on_layer("under", (1, 0)), '!');
+}
+
+#[test]
+fn test_shapes() {
+    let mut canvas = Canvas::new('.', (0, 0));
+    canvas.fill_diamond((0, 0), 2, '#');
+    canvas.fill_rect((3, 1), (2, 2), 'r');
+    canvas.fill_circle((-5, -5), 1, 'o');
+
+    assert_eq!(
+        canvas.to_string(),
+        [
+            ".o", //
+            "ooo",
+            ".o",
+            "",
+            "......#",
+            ".....###",
+            "....#####",
+            ".....###rr",
+            "......#.rr",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_flood_fill() {
+    let mut canvas = Canvas::from_lines(
+        (0, 0),
+        ["#####", "#   #", "# # #", "#  ##", "#####"],
+    );
+
+    assert_eq!(canvas.flood_fill((1, 1), '~', &['#']), 7);
+    assert_eq!(canvas.get((3, 2)), '~');
+
+    // Filling again finds nothing new
+    assert_eq!(canvas.flood_fill((1, 1), '~', &['#']), 0);
+
+    // Only measures when drawing is off
+    canvas.draw_enabled = false;
+    assert_eq!(canvas.flood_fill((1, 1), 'x', &['#']), 7);
+    assert_eq!(canvas.get((1, 1)), '~');
+}
@@ -0,0 +1,211 @@
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    ops::{Add, Div, Mul, Rem, Sub},
+};
+
+use crate::helpers::is_checked;
+
+/// Ways integer math can go wrong without anyone noticing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArithmeticError {
+    Overflow { op: char, lhs: String, rhs: String },
+    DivisionByZero { lhs: String },
+    InexactDivision { lhs: String, rhs: String },
+}
+
+impl ArithmeticError {
+    /// Attach a description of what the solver was doing when things broke
+    pub fn context(self, context: impl Into<String>) -> SolverError {
+        SolverError {
+            context: context.into(),
+            reason: self,
+        }
+    }
+}
+
+impl Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ArithmeticError::*;
+
+        match self {
+            Overflow { op, lhs, rhs } => {
+                write!(f, "{lhs} {op} {rhs} overflows")
+            }
+            DivisionByZero { lhs } => write!(f, "{lhs} / 0 is undefined"),
+            InexactDivision { lhs, rhs } => {
+                write!(f, "{lhs} / {rhs} leaves a remainder")
+            }
+        }
+    }
+}
+
+impl Error for ArithmeticError {}
+
+/// Arithmetic error along with where in the solver it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverError {
+    pub context: String,
+    pub reason: ArithmeticError,
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.context, self.reason)
+    }
+}
+
+impl Error for SolverError {}
+
+/// Answer from a solver that can fail in checked mode. Shows the error in
+/// place of the answer so the runner doesn't need to know the difference
+#[derive(Debug)]
+pub struct Answer<T>(pub Result<T, SolverError>);
+
+impl<T: Display> Display for Answer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Ok(answer) => write!(f, "{answer}"),
+            Err(e) => write!(f, "💥 {e}"),
+        }
+    }
+}
+
+impl<T> From<Result<T, SolverError>> for Answer<T> {
+    fn from(result: Result<T, SolverError>) -> Self {
+        Self(result)
+    }
+}
+
+/// Integers that can do their math either the fast way or the careful way
+pub trait CheckedInt:
+    Copy
+    + Display
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! checked_int {
+    ($($t:ty),+) => {
+        $(
+            impl CheckedInt for $t {
+                const ZERO: Self = 0;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )+
+    };
+}
+
+checked_int!(i32, i64, u32, u64, u128);
+
+fn overflow<T: Display>(op: char, lhs: T, rhs: T) -> ArithmeticError {
+    ArithmeticError::Overflow {
+        op,
+        lhs: lhs.to_string(),
+        rhs: rhs.to_string(),
+    }
+}
+
+/// `lhs + rhs`, or an error if it overflows
+pub fn strict_add<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    lhs.checked_add(rhs).ok_or_else(|| overflow('+', lhs, rhs))
+}
+
+/// `lhs - rhs`, or an error if it overflows
+pub fn strict_sub<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    lhs.checked_sub(rhs).ok_or_else(|| overflow('-', lhs, rhs))
+}
+
+/// `lhs * rhs`, or an error if it overflows
+pub fn strict_mul<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    lhs.checked_mul(rhs).ok_or_else(|| overflow('*', lhs, rhs))
+}
+
+/// `lhs / rhs`, or an error unless it comes out even
+pub fn strict_div<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    if rhs == T::ZERO {
+        Err(ArithmeticError::DivisionByZero {
+            lhs: lhs.to_string(),
+        })
+    } else if lhs % rhs != T::ZERO {
+        Err(ArithmeticError::InexactDivision {
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+        })
+    } else {
+        Ok(lhs / rhs)
+    }
+}
+
+/// `lhs + rhs`, refusing to overflow in checked mode
+pub fn add<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    if !is_checked() {
+        return Ok(lhs + rhs);
+    }
+    strict_add(lhs, rhs)
+}
+
+/// `lhs - rhs`, refusing to overflow in checked mode
+pub fn sub<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    if !is_checked() {
+        return Ok(lhs - rhs);
+    }
+    strict_sub(lhs, rhs)
+}
+
+/// `lhs * rhs`, refusing to overflow in checked mode
+pub fn mul<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    if !is_checked() {
+        return Ok(lhs * rhs);
+    }
+    strict_mul(lhs, rhs)
+}
+
+/// `lhs / rhs` for divisions that are supposed to come out even. In checked
+/// mode a remainder is an error rather than something to round away
+pub fn div<T: CheckedInt>(lhs: T, rhs: T) -> Result<T, ArithmeticError> {
+    if !is_checked() {
+        return Ok(lhs / rhs);
+    }
+    strict_div(lhs, rhs)
+}
+
+#[test]
+fn test_checked_arithmetic() {
+    // The strict versions are what checked mode runs, without having to flip
+    // the global switch for every other test
+    assert_eq!(strict_add(2_i64, 3), Ok(5));
+    assert_eq!(strict_mul(6_u64, 7), Ok(42));
+    assert_eq!(strict_div(42_i64, -7), Ok(-6));
+    assert_eq!(strict_mul(u128::MAX, 2), Err(overflow('*', u128::MAX, 2)));
+    assert_eq!(strict_sub(0_u32, 1), Err(overflow('-', 0, 1)));
+    assert_eq!(
+        strict_div(7_i64, 2).map_err(|e| e.context("monkey root").to_string()),
+        Err("monkey root: 7 / 2 leaves a remainder".to_owned())
+    );
+    assert!(matches!(
+        strict_div(7_i32, 0),
+        Err(ArithmeticError::DivisionByZero { .. })
+    ));
+}
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::geometry::{Point2, Point3};
+
+/// Disjoint sets of the numbers `0..len` that can be merged together
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    /// Add a new set with a single element, returning the element
+    pub fn add(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.size.push(1);
+        self.parent.len() - 1
+    }
+
+    /// Element standing in for the whole set the given element is in
+    pub fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merge the sets holding the two elements, returning true if they were
+    /// separate before
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        let (big, small) = if self.size[a] < self.size[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        true
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the set holding the given element
+    pub fn size_of(&mut self, i: usize) -> usize {
+        let root = self.find(i);
+        self.size[root]
+    }
+}
+
+/// Points that can be grouped into connected components
+pub trait Lattice: Copy + Eq + Hash {
+    /// Points directly connected to this one
+    fn adjacent(self) -> impl Iterator<Item = Self>;
+
+    fn lower(self, other: Self) -> Self;
+
+    fn upper(self, other: Self) -> Self;
+
+    fn on_boundary(self, min: Self, max: Self) -> bool;
+}
+
+impl Lattice for Point2 {
+    fn adjacent(self) -> impl Iterator<Item = Self> {
+        self.neighbors4()
+    }
+
+    fn lower(self, other: Self) -> Self {
+        self.min(other)
+    }
+
+    fn upper(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    fn on_boundary(self, min: Self, max: Self) -> bool {
+        Point2::on_boundary(&self, min, max)
+    }
+}
+
+impl Lattice for Point3 {
+    fn adjacent(self) -> impl Iterator<Item = Self> {
+        self.neighbors6()
+    }
+
+    fn lower(self, other: Self) -> Self {
+        self.min(other)
+    }
+
+    fn upper(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    fn on_boundary(self, min: Self, max: Self) -> bool {
+        Point3::on_boundary(&self, min, max)
+    }
+}
+
+/// A group of points that are all connected to each other
+#[derive(Debug, Clone)]
+pub struct Component<P> {
+    pub cells: Vec<P>,
+
+    /// Corners of the smallest box holding every cell
+    pub min: P,
+    pub max: P,
+
+    /// Whether any cell lies on the edge of the region that was labeled
+    pub touches_boundary: bool,
+}
+
+impl<P> Component<P> {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// Set of points split into connected components
+#[derive(Debug)]
+pub struct Components<P> {
+    components: Vec<Component<P>>,
+    labels: HashMap<P, usize>,
+}
+
+impl<P: Lattice> Components<P> {
+    /// Group the given cells into components. `min` and `max` are the corners
+    /// of the region the cells came from, used to decide which components
+    /// touch its boundary
+    pub fn label<I>(cells: I, min: P, max: P) -> Self
+    where
+        I: IntoIterator<Item = P>,
+    {
+        let mut index = HashMap::new();
+        let mut cells_in_order = vec![];
+        let mut sets = UnionFind::new(0);
+
+        for cell in cells {
+            if index.contains_key(&cell) {
+                continue;
+            }
+
+            let i = sets.add();
+            index.insert(cell, i);
+            cells_in_order.push(cell);
+
+            for neighbor in cell.adjacent() {
+                if let Some(j) = index.get(&neighbor) {
+                    sets.union(i, *j);
+                }
+            }
+        }
+
+        let mut components: Vec<Component<P>> = vec![];
+        let mut root_to_label = HashMap::new();
+        let mut labels = HashMap::with_capacity(cells_in_order.len());
+
+        for (i, cell) in cells_in_order.into_iter().enumerate() {
+            let label =
+                *root_to_label.entry(sets.find(i)).or_insert_with(|| {
+                    components.push(Component {
+                        cells: vec![],
+                        min: cell,
+                        max: cell,
+                        touches_boundary: false,
+                    });
+                    components.len() - 1
+                });
+
+            let component = &mut components[label];
+            component.cells.push(cell);
+            component.min = component.min.lower(cell);
+            component.max = component.max.upper(cell);
+            component.touches_boundary |= cell.on_boundary(min, max);
+
+            labels.insert(cell, label);
+        }
+
+        Self { components, labels }
+    }
+
+    /// Index of the component holding the given cell
+    pub fn label_of(&self, cell: &P) -> Option<usize> {
+        self.labels.get(cell).copied()
+    }
+
+    pub fn get(&self, label: usize) -> &Component<P> {
+        &self.components[label]
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Component<P>> {
+        self.components.iter()
+    }
+}
+
+#[test]
+fn test_union_find() {
+    let mut sets = UnionFind::new(5);
+
+    assert!(sets.union(0, 1));
+    assert!(sets.union(3, 4));
+    assert!(!sets.union(1, 0));
+    assert!(sets.same(0, 1) && !sets.same(1, 3));
+
+    let extra = sets.add();
+    sets.union(extra, 4);
+    assert_eq!(sets.size_of(3), 3);
+    assert_eq!(sets.size_of(2), 1);
+}
+
+#[test]
+fn test_components() {
+    let grid = ["#.###", "#..##", "###.#", "#####"];
+    let open = grid.iter().enumerate().flat_map(|(y, row)| {
+        row.chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '.')
+            .map(move |(x, _)| Point2::new(x as i32, y as i32))
+    });
+
+    let open = Components::label(open, Point2::ORIGIN, Point2::new(4, 3));
+    assert_eq!(open.len(), 2);
+
+    let edge = open.get(open.label_of(&Point2::new(1, 0)).expect("🧱"));
+    assert_eq!(edge.size(), 3);
+    assert_eq!((edge.min, edge.max), (Point2::new(1, 0), Point2::new(2, 1)));
+    assert!(edge.touches_boundary);
+
+    let pocket = open.get(open.label_of(&Point2::new(3, 2)).expect("🧱"));
+    assert_eq!(pocket.size(), 1);
+    assert!(!pocket.touches_boundary);
+    assert_eq!(open.label_of(&Point2::ORIGIN), None);
+}
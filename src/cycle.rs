@@ -0,0 +1,100 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// A simulation found to repeat itself, along with the history of some
+/// additive metric (height, score, ...) needed to predict it far into the
+/// future
+#[derive(Debug)]
+pub struct Cycle {
+    /// Number of steps before the repeating part starts
+    pub start: usize,
+
+    /// Number of steps in one repeat
+    pub length: usize,
+
+    /// Metric after each number of steps, starting with zero steps
+    values: Vec<i64>,
+}
+
+impl Cycle {
+    /// Value of the metric after the given number of steps
+    pub fn extrapolate(&self, steps: usize) -> i64 {
+        if steps < self.values.len() {
+            return self.values[steps];
+        }
+
+        let repeats = (steps - self.start) / self.length;
+        let remainder = (steps - self.start) % self.length;
+        let gain_per_repeat =
+            self.values[self.start + self.length] - self.values[self.start];
+
+        self.values[self.start + remainder] + repeats as i64 * gain_per_repeat
+    }
+}
+
+/// Run a simulation one step at a time until the state after a step matches
+/// the state after some earlier step. The `step` closure is given the number
+/// of the step being run (starting at 1) and returns a hashable snapshot of
+/// everything that determines the future of the simulation along with the
+/// value of the metric after the step. The metric starts at `initial` before
+/// any steps are run. Gives up after `max_steps`
+pub fn find_cycle<K, F>(
+    initial: i64,
+    max_steps: usize,
+    mut step: F,
+) -> Option<Cycle>
+where
+    K: Hash + Eq,
+    F: FnMut(usize) -> (K, i64),
+{
+    let mut seen = HashMap::new();
+    let mut values = vec![initial];
+
+    for n in 1..=max_steps {
+        let (state, value) = step(n);
+        values.push(value);
+
+        if let Some(start) = seen.insert(state, n) {
+            return Some(Cycle {
+                start,
+                length: n - start,
+                values,
+            });
+        }
+    }
+
+    None
+}
+
+#[test]
+fn test_find_cycle() {
+    // 2 steps of run-up, then the state cycles with period 3 gaining 10 each
+    // time around. The first state seen again is the one after step 3
+    let states = [7, 8, 0, 1, 2];
+    let gains = [1, 1, 2, 3, 5];
+
+    let mut total = 0;
+    let cycle = find_cycle(0, 100, |n| {
+        let i = if n <= 2 { n - 1 } else { 2 + (n - 3) % 3 };
+        total += gains[i];
+        (states[i], total)
+    })
+    .expect("Cycle exists");
+
+    assert_eq!((cycle.start, cycle.length), (3, 3));
+
+    // Brute force the same metric to compare against
+    let brute = |steps: usize| {
+        (1..=steps)
+            .map(|n| if n <= 2 { 1 } else { gains[2 + (n - 3) % 3] })
+            .sum::<i64>()
+    };
+
+    for steps in [0, 1, 4, 5, 6, 17, 1000] {
+        assert_eq!(cycle.extrapolate(steps), brute(steps));
+    }
+}
+
+#[test]
+fn test_no_cycle() {
+    assert!(find_cycle(0, 50, |n| (n, n as i64)).is_none());
+}
@@ -1,17 +1,125 @@
-use std::fmt::Display;
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Display};
 
-pub fn problem_1<I>(input_lines: I) -> impl Display
+use iter_tools::Itertools;
+
+use crate::helpers::is_report;
+
+/// Number of elves whose calories get added up for problem 2
+const TOP_ELVES: usize = 3;
+
+/// Total calories carried by each elf, in the order they appear. Extra blank
+/// lines don't count as elves carrying nothing
+fn elf_totals<I>(input_lines: I) -> impl Iterator<Item = i32>
 where
     I: Iterator<Item = String>,
 {
     input_lines
         .map(|line| line.parse::<i32>().ok())
         .chain(Some(None)) // Count the last elf
-        .fold((0, 0), |(max, curr), calories_opt| match calories_opt {
-            Some(calories) => (max, curr + calories),
-            None => (max.max(curr), 0),
-        })
-        .0
+        .scan(
+            None,
+            |curr: &mut Option<i32>, calories_opt| match calories_opt {
+                Some(calories) => {
+                    *curr = Some(curr.unwrap_or_default() + calories);
+                    Some(None)
+                }
+                None => Some(curr.take()),
+            },
+        )
+        .flatten()
+}
+
+/// Largest `k` totals, biggest first. Only the best `k` seen so far are kept
+/// around, so this works on as many elves as you can throw at it
+fn top_k<I>(totals: I, k: usize) -> Vec<i32>
+where
+    I: Iterator<Item = i32>,
+{
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+
+    for total in totals {
+        heap.push(Reverse(total));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(t)| t)
+        .collect()
+}
+
+/// How one elf stacks up against the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ElfReport {
+    /// Position of the elf in the input, starting at 0
+    index: usize,
+    total: i32,
+
+    /// 1 for the elf with the most calories. Elves with the same total share
+    /// a rank
+    rank: usize,
+}
+
+impl Display for ElfReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Elf {}: {} calories (#{})",
+            self.index, self.total, self.rank
+        )
+    }
+}
+
+/// Report for every elf, in input order
+fn elf_report<I>(input_lines: I) -> Vec<ElfReport>
+where
+    I: Iterator<Item = String>,
+{
+    let totals = elf_totals(input_lines).collect_vec();
+    let mut ranks = vec![0; totals.len()];
+
+    let mut by_total = (0..totals.len()).collect_vec();
+    by_total.sort_by_key(|i| Reverse(totals[*i]));
+
+    for (position, i) in by_total.iter().enumerate() {
+        ranks[*i] = match position {
+            0 => 1,
+            _ if totals[by_total[position - 1]] == totals[*i] => {
+                ranks[by_total[position - 1]]
+            }
+            _ => position + 1,
+        };
+    }
+
+    totals
+        .into_iter()
+        .zip(ranks)
+        .enumerate()
+        .map(|(index, (total, rank))| ElfReport { index, total, rank })
+        .collect()
+}
+
+pub fn problem_1<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    // Only the report needs the whole input at once
+    let most = if is_report() {
+        let input_lines = input_lines.collect_vec();
+
+        println!();
+        for report in elf_report(input_lines.iter().cloned()) {
+            println!("{report}");
+        }
+
+        top_k(elf_totals(input_lines.into_iter()), 1)
+    } else {
+        top_k(elf_totals(input_lines), 1)
+    };
+
+    most.into_iter().sum::<i32>()
 }
 
 /**** Problem 2 ******/
@@ -20,16 +128,29 @@ pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let (a, b, c, _) = input_lines
-        .map(|line| line.parse::<i32>().ok())
-        .chain(Some(None)) // Count the last elf
-        .fold((0, 0, 0, 0), |(max_1, max_2, max_3, curr), v| match v {
-            Some(c) => (max_1, max_2, max_3, curr + c),
-            None if curr > max_1 => (curr, max_1, max_2, 0),
-            None if curr > max_2 => (max_1, curr, max_2, 0),
-            None if curr > max_3 => (max_1, max_2, curr, 0),
-            _ => (max_1, max_2, max_3, 0),
-        });
-
-    a + b + c
+    top_k(elf_totals(input_lines), TOP_ELVES)
+        .into_iter()
+        .sum::<i32>()
+}
+
+#[test]
+fn test_top_k_and_report() {
+    let lines = || {
+        [
+            "1000", "2000", "", "4000", "", "", "3000", "", "500", "2500", "",
+            "1", "",
+        ]
+        .into_iter()
+        .map(str::to_owned)
+    };
+
+    assert_eq!(top_k(elf_totals(lines()), 2), vec![4000, 3000]);
+    assert_eq!(top_k(elf_totals(lines()), 10).len(), 5);
+
+    let report = elf_report(lines());
+    assert_eq!(
+        report.iter().map(|r| r.rank).collect_vec(),
+        vec![2, 1, 2, 2, 5]
+    );
+    assert_eq!(report[4].to_string(), "Elf 4: 1 calories (#5)");
 }
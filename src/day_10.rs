@@ -135,9 +135,12 @@ impl Sprite {
     }
 }
 
+/// Shared pixel buffer written by the renderer
+type ScreenBuffer = Rc<RefCell<Vec<char>>>;
+
 /// Create a rendering system that is composed of a closure for drawing and a
 /// readable buffer for viewing
-fn create_renderer() -> (Rc<RefCell<Vec<char>>>, impl FnMut((Cycle, Sprite))) {
+fn create_renderer() -> (ScreenBuffer, impl FnMut((Cycle, Sprite))) {
     let buffer = Rc::new(RefCell::new(vec![' '; 6 * 40]));
 
     (buffer.clone(), move |(Cycle { number, .. }, sprite)| {
@@ -2,14 +2,18 @@ use iter_tools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{anychar, char, space0, space1, u128, u32},
+    character::complete::{anychar, char, space1, u128, u32},
     combinator::map,
     multi::separated_list1,
-    sequence::{preceded, separated_pair, terminated, tuple},
+    sequence::{preceded, separated_pair, tuple},
     IResult,
 };
 use std::fmt::Display;
 
+use crate::checked::{add, mul, Answer, ArithmeticError, SolverError};
+use crate::helpers::is_checked;
+use crate::parsing::{between, blocks};
+
 /// This big type made clippy angry
 type ParseResult<'a> = IResult<&'a str, (u32, Vec<Item>, Op, u128, u32, u32)>;
 
@@ -31,6 +35,9 @@ type ParseResult<'a> = IResult<&'a str, (u32, Vec<Item>, Op, u128, u32, u32)>;
 /// going to u128 whereas the original method was done with i32s
 ///
 /// 2^5 * 3 ^3 * 5^2 * 7 * 11 * 13 * 17 * 19 * 23 * 29 * 31 * 37 * 41 * 43 * 47
+///
+/// In checked mode this gets swapped for the product of the divisors that
+/// actually show up in the input, so nothing depends on it being right
 const MAGIC_NUMBER: u128 = 9_419_588_158_802_421_600;
 
 /// Operations a monkey can do?
@@ -46,13 +53,15 @@ enum Op {
 }
 
 impl Op {
-    fn apply(&self, lhs: u128) -> u128 {
+    fn apply(&self, lhs: u128) -> Result<u128, ArithmeticError> {
         match self {
-            Op::Add(rhs) => lhs + rhs,
-            Op::Mul(rhs) => lhs * rhs,
-            Op::Square => lhs * lhs,
-            Op::Double => lhs + lhs,
-            Op::Div(rhs) => lhs / rhs,
+            Op::Add(rhs) => add(lhs, *rhs),
+            Op::Mul(rhs) => mul(lhs, *rhs),
+            Op::Square => mul(lhs, lhs),
+            Op::Double => add(lhs, lhs),
+
+            // Stress relief is supposed to round down
+            Op::Div(rhs) => Ok(lhs / rhs),
         }
     }
 }
@@ -67,9 +76,16 @@ impl Item {
         Self(initial_value)
     }
 
-    /// Apply the given operation to this item's stress level.
-    fn apply(&mut self, op: Op) {
-        self.0 = op.apply(self.0) % MAGIC_NUMBER;
+    /// Apply the given operation to this item's stress level, keeping it
+    /// below the modulus if there is one
+    fn apply(
+        &mut self,
+        op: Op,
+        modulus: Option<u128>,
+    ) -> Result<(), ArithmeticError> {
+        let stress = op.apply(self.0)?;
+        self.0 = modulus.map_or(stress, |m| stress % m);
+        Ok(())
     }
 
     fn modulo(&mut self, divisor: u128) -> u128 {
@@ -95,36 +111,58 @@ impl Monkey {
     fn inspect(
         &mut self,
         relieve_stress: bool,
-    ) -> impl Iterator<Item = (usize, Item)> + '_ {
+        modulus: Option<u128>,
+    ) -> impl Iterator<Item = Result<(usize, Item), ArithmeticError>> + '_ {
+        let op = self.op;
+
         self.items
             .drain(..)
-            .map(|mut item| {
-                item.apply(self.op);
-                item
-            })
             .map(move |mut item| {
-                relieve_stress.then(|| item.apply(Op::Div(3)));
-                item
+                item.apply(op, modulus)?;
+                if relieve_stress {
+                    item.apply(Op::Div(3), modulus)?;
+                }
+                Ok(item)
             })
-            .map(|mut item| {
+            .map(|item| {
+                let mut item = item?;
                 self.inspect_count += 1;
                 if item.modulo(self.modulo) == 0 {
-                    (self.true_target, item)
+                    Ok((self.true_target, item))
                 } else {
-                    (self.false_target, item)
+                    Ok((self.false_target, item))
                 }
             })
     }
 }
 
-/****************** Parsing ******************/
+/// Number every stress level can be reduced by without changing where any
+/// item gets thrown. Normally that's the magic number, but in checked mode
+/// it's worked out from the monkeys themselves, and nothing gets reduced at
+/// all when stress relief is on since dividing doesn't play nicely with modulo
+fn stress_modulus(
+    monkeys: &[Monkey],
+    relieve_stress: bool,
+) -> Result<Option<u128>, SolverError> {
+    if !is_checked() {
+        return Ok(Some(MAGIC_NUMBER));
+    }
 
-macro_rules! between {
-    ($before:expr, $pat:expr, $after:expr) => {
-        preceded($before, terminated($pat, terminated($after, space0)))
-    };
+    if relieve_stress {
+        return Ok(None);
+    }
+
+    monkeys
+        .iter()
+        .map(|m| m.modulo)
+        .unique()
+        .try_fold(1, mul)
+        .map(Some)
+        .map_err(|e| e.context("combining the monkeys' divisors"))
 }
 
+/****************** Parsing ******************/
+
 fn parse_monkey_id(input: &str) -> IResult<&'_ str, u32> {
     between!(tag("Monkey "), u32, tag(":\n"))(input)
 }
@@ -210,36 +248,40 @@ macro_rules! run_monkeys {
     };
 
     (__internal, $input_lines:ident, $rounds:literal, $stress_relief:literal) => {{
-        let mut line_group = 0;
-        let groups = $input_lines.group_by(|line| {
-            line.is_empty().then(|| line_group += 1);
-            line_group
-        });
-
-        let mut monkeys = groups
-            .into_iter()
-            .map(|group| group.1.filter(|line| !line.is_empty()).join("\n"))
+        let mut monkeys = blocks($input_lines)
+            .map(|block| block.iter().map(|line| line.trim()).join("\n"))
             .map(parse_monkey)
             .collect::<Vec<_>>();
 
         let mut throws = vec![];
 
-        (0..$rounds).for_each(|_| {
-            for monkey_id in 0..monkeys.len() {
-                throws.extend(monkeys[monkey_id].inspect($stress_relief));
-                throws
-                    .drain(..)
-                    .for_each(|(id, item)| monkeys[id].items.push(item));
-            }
+        let result = stress_modulus(&monkeys, $stress_relief).and_then(|m| {
+            (1..=$rounds).try_for_each(|round| {
+                for monkey_id in 0..monkeys.len() {
+                    for throw in monkeys[monkey_id].inspect($stress_relief, m) {
+                        throws.push(throw.map_err(|e| {
+                            e.context(format!(
+                                "round {round}, monkey {monkey_id}"
+                            ))
+                        })?);
+                    }
+                    throws
+                        .drain(..)
+                        .for_each(|(id, item)| monkeys[id].items.push(item));
+                }
+                Ok(())
+            })
         });
 
-        monkeys
-            .iter()
-            .map(|m| m.inspect_count)
-            .sorted()
-            .rev()
-            .take(2)
-            .product::<usize>()
+        Answer(result.map(|_| {
+            monkeys
+                .iter()
+                .map(|m| m.inspect_count)
+                .sorted()
+                .rev()
+                .take(2)
+                .product::<usize>()
+        }))
     }};
 }
 
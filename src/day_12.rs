@@ -1,5 +1,8 @@
 use iter_tools::Itertools;
-use std::{collections::LinkedList, fmt::Display, iter::once};
+use std::fmt::Display;
+
+use crate::parsing::CharGrid;
+use crate::search::{bfs, multi_bfs};
 
 /// Basically an image
 struct HeightMap {
@@ -8,37 +11,31 @@ struct HeightMap {
     height: usize,
 }
 
-fn parse_map<I>(mut input_lines: I) -> HeightMap
+fn parse_map<I>(input_lines: I) -> HeightMap
 where
     I: Iterator<Item = String>,
 {
-    let first_line = input_lines.next().expect("🧐");
-    let width = first_line.len();
-
-    let buffer = once(first_line)
-        .chain(input_lines)
-        .map(String::into_bytes)
-        .flat_map(Vec::into_iter)
-        .map(|c| match c {
-            b'S' => b'a' - 1,
-            b'E' => b'z' + 1,
-            _ => c,
+    let grid = CharGrid::from_lines(input_lines, 'a');
+
+    let buffer = grid
+        .iter()
+        .map(|(_, c)| match c {
+            'S' => b'a' - 1,
+            'E' => b'z' + 1,
+            _ => c as u8,
         })
         .collect::<Vec<_>>();
 
-    let height = buffer.len() / width;
-
     HeightMap {
         buffer,
-        width,
-        height,
+        width: grid.width,
+        height: grid.height,
     }
 }
 
 struct MapNode {
     elev: u8,
     neighbors: Vec<usize>,
-    visited_distance: Option<i32>,
 }
 
 impl HeightMap {
@@ -85,38 +82,6 @@ impl HeightMap {
         MapNode {
             elev: curr_elev,
             neighbors,
-            visited_distance: None,
-        }
-    }
-}
-
-/// Search the graph with the closest nodes first starting and ending where
-/// specified and return the distance to the end node (if a route is possible)
-fn bfs(start: usize, end: usize, graph: &mut [MapNode]) -> Option<i32> {
-    // Clear the nodes before starting since we might be reusing this graph
-    graph.iter_mut().for_each(|n| n.visited_distance = None);
-
-    let mut queue = LinkedList::new();
-    queue.push_back((start, 0));
-
-    loop {
-        if let Some((curr_idx, dist)) = queue.pop_front() {
-            let curr = &mut graph[curr_idx];
-
-            if curr.visited_distance.is_some() {
-                // No need to repeat a visit
-                continue;
-            } else {
-                curr.visited_distance = Some(dist)
-            }
-
-            if curr_idx == end {
-                break Some(dist);
-            }
-
-            queue.extend(curr.neighbors.iter().map(|n| (*n, dist + 1)));
-        } else {
-            break None;
         }
     }
 }
@@ -127,10 +92,13 @@ pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let (start, end, mut graph) = parse_map(input_lines).into_graph();
+    let (start, end, graph) = parse_map(input_lines).into_graph();
+    let neighbors = |i: &usize| graph[*i].neighbors.iter().copied();
 
     // bfs from the start to the end to get the final distance
-    bfs(start, end, &mut graph).expect("🥸")
+    bfs(start, &neighbors, |i| *i == end)
+        .goal_distance()
+        .expect("🥸")
 }
 
 /**** Problem 2 ******/
@@ -141,10 +109,8 @@ where
 {
     let map = parse_map(input_lines);
 
-    // This is kind of a brute force method. We look for all the `'a'`s in the
-    // map and do a bfs for all of them and find the shortest path. A better way
-    // would be to do a single bfs from the end and stop the search whenever an
-    // `'a'` is hit
+    // Search outward from all the `'a'`s at once, so the first time we reach
+    // the end is along the shortest path from any of them
     let possible_starts = map
         .buffer
         .iter()
@@ -152,11 +118,10 @@ where
         .filter_map(|(i, c)| (*c == b'a').then_some(i))
         .collect_vec();
 
-    let (_, end, mut graph) = map.into_graph();
+    let (_, end, graph) = map.into_graph();
+    let neighbors = |i: &usize| graph[*i].neighbors.iter().copied();
 
-    possible_starts
-        .into_iter()
-        .filter_map(|start| bfs(start, end, &mut graph))
-        .min()
+    multi_bfs(possible_starts, &neighbors, |i| *i == end)
+        .goal_distance()
         .expect("😡")
 }
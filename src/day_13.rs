@@ -4,11 +4,12 @@ use nom::{
     character::complete::{char, i32},
     combinator::map,
     multi::separated_list0,
-    sequence::{preceded, terminated},
     IResult,
 };
 use std::{cmp::Ordering, fmt::Display};
 
+use crate::parsing::between;
+
 /// Representation of a packet
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum Packet {
@@ -18,12 +19,6 @@ enum Packet {
 
 /************* Nom nom nom nom! ***********/
 
-macro_rules! between {
-    ($before:expr, $pat:expr, $after:expr) => {
-        preceded($before, terminated($pat, $after))
-    };
-}
-
 fn parse_number_packet(input: &str) -> IResult<&'_ str, Packet> {
     map(i32, Packet::Num)(input)
 }
@@ -81,13 +76,13 @@ fn compare(left: &Packet, right: &Packet) -> Ordering {
 /// Add some rustisms to make sorting in iterators work
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(compare(self, other))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Packet {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("🥲")
+        compare(self, other)
     }
 }
 
@@ -1,35 +1,39 @@
 use iter_tools::Itertools;
-use nom::{
-    bytes::complete::tag, character::complete::char, character::complete::i32,
-    multi::separated_list1, sequence::separated_pair, IResult,
-};
+use nom::{bytes::complete::tag, multi::separated_list1, IResult};
 use std::fmt::Display;
 
 use crate::canvas::Canvas;
+use crate::geometry::Point2;
+use crate::parsing::{Parsable, ParseLines};
+use crate::search::dfs;
 
 /// Yup
-fn parse_line(line: String) -> Vec<(i32, i32)> {
-    let result: IResult<&str, Vec<(i32, i32)>> = separated_list1(
-        tag(" -> "),
-        separated_pair(i32, char(','), i32),
-    )(&line);
+fn parse_path(input: &str) -> IResult<&str, Vec<Point2>> {
+    separated_list1(tag(" -> "), Point2::parse)(input)
+}
 
-    result.expect("😫").1
+fn parse_paths<I>(lines: I) -> impl Iterator<Item = Vec<Point2>>
+where
+    I: Iterator<Item = String>,
+{
+    lines
+        .parse_lines_with(parse_path)
+        .map(|path| path.expect("😫"))
 }
 
 /// Create a canvas to display the state of the sand
 fn create_canvas<I>(lines: I, bottom_line: bool) -> Canvas
 where
-    I: Iterator<Item = Vec<(i32, i32)>>,
+    I: Iterator<Item = Vec<Point2>>,
 {
     let mut result = Canvas::new(' ', (500, 0));
 
     // Draw all the rocks defined in the problem input
     lines
-        .flat_map(|vec| {
-            vec.into_iter().tuple_windows::<((i32, i32), (i32, i32))>()
-        })
-        .for_each(|(start, end)| result.draw_line(start, end, '█'));
+        .flat_map(|vec| vec.into_iter().tuple_windows::<(Point2, Point2)>())
+        .for_each(|(start, end)| {
+            result.draw_line(start.into(), end.into(), '█')
+        });
 
     if bottom_line {
         let floor_y = result.top_left.1 + result.height + 1;
@@ -71,11 +75,11 @@ struct GraphNode {
 }
 
 fn create_graph(canvas: &Canvas) -> (usize, Vec<GraphNode>) {
-    let mut result = Vec::with_capacity(canvas.contents.len());
+    let mut result = Vec::with_capacity(canvas.len());
     let start = canvas.index_for_coordinate((500, 0));
 
-    result.extend(canvas.contents.iter().enumerate().map(|(i, p)| {
-        if *p != ' ' {
+    result.extend((0..canvas.len()).map(|i| {
+        if canvas.get(canvas.coordinate_from_index(i)) != ' ' {
             return GraphNode {
                 end: true,
                 children: vec![],
@@ -87,7 +91,7 @@ fn create_graph(canvas: &Canvas) -> (usize, Vec<GraphNode>) {
         let down_and_right = down + 1;
         let children = [down, down_and_left, down_and_right]
             .into_iter()
-            .map(|j| (j, canvas.contents[j]))
+            .map(|j| (j, canvas.get(canvas.coordinate_from_index(j))))
             .filter(|(_, c)| *c != '█')
             .map(|(j, _)| j)
             .collect_vec();
@@ -101,50 +105,31 @@ fn create_graph(canvas: &Canvas) -> (usize, Vec<GraphNode>) {
     (start, result)
 }
 
-/// Perform a dfs of the graph from the given start point and return the number
-/// of steps to get to a node marked `end`
-fn dfs(
+/// Pour sand in from the start and count the grains that come to rest. Sand
+/// settles in the order a dfs finishes with each spot, so the count is either
+/// everything finished before the first grain falls into the abyss (an `end`
+/// node) or, with the abyss cut off, every spot the sand can reach
+fn pour_sand(
     start: usize,
     graph: &[GraphNode],
     canvas: &mut Canvas,
     stop_on_end: bool,
 ) -> usize {
-    let mut stack = vec![start];
-    let mut count = 0;
-
-    let mut children_to_check = vec![];
-    while !stack.is_empty() {
-        let curr_index = *(stack.last().expect("🥶"));
-        let curr = &graph[curr_index];
-
-        if curr.end {
-            if stop_on_end {
-                break;
-            }
-            stack.pop();
-            continue;
-        }
-
-        children_to_check.extend(
-            curr.children
-                .iter()
-                .map(|c| (*c, canvas.contents[*c]))
-                .filter(|(_, v)| stop_on_end || *v != '~')
-                .filter(|(_, v)| *v != 'o')
-                .map(|(c, _)| c)
-                .rev(),
-        );
-
-        if children_to_check.is_empty() {
-            stack.pop();
-            count += 1;
-            canvas.contents[curr_index] = 'o';
-        } else {
-            stack.append(&mut children_to_check);
-        }
-    }
+    let children = |i: &usize| {
+        graph[*i]
+            .children
+            .iter()
+            .copied()
+            .filter(move |c| stop_on_end || !graph[*c].end)
+    };
+
+    let result = dfs(start, &children, |i| graph[*i].end);
+
+    result.finished.iter().for_each(|i| {
+        canvas.draw_point(canvas.coordinate_from_index(*i), 'o');
+    });
 
-    count
+    result.finished.len()
 }
 
 /**** Problem 1 ******/
@@ -153,11 +138,11 @@ pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut canvas = create_canvas(input_lines.map(parse_line), false);
+    let mut canvas = create_canvas(parse_paths(input_lines), false);
 
     let (start, graph) = create_graph(&canvas);
 
-    let result = dfs(start, &graph, &mut canvas, true);
+    let result = pour_sand(start, &graph, &mut canvas, true);
 
     canvas.render();
 
@@ -170,13 +155,41 @@ pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut canvas = create_canvas(input_lines.map(parse_line), true);
+    let mut canvas = create_canvas(parse_paths(input_lines), true);
 
     let (start, graph) = create_graph(&canvas);
 
-    let result = dfs(start, &graph, &mut canvas, false);
+    let result = pour_sand(start, &graph, &mut canvas, false);
 
     canvas.render();
 
     result
 }
+
+#[test]
+fn test_example_snapshots() {
+    use crate::helpers::read_lines;
+
+    for (floor, top_left, snapshot) in [
+        (false, (493, -1), "input/day_14_example_snapshot_1.txt"),
+        (true, (487, -1), "input/day_14_example_snapshot_2.txt"),
+    ] {
+        let lines = read_lines("input/day_14_example.txt")
+            .expect("Example input exists")
+            .map(|line| line.expect("Readable example"));
+
+        let mut canvas = create_canvas(parse_paths(lines), floor);
+        let (start, graph) = create_graph(&canvas);
+        pour_sand(start, &graph, &mut canvas, !floor);
+
+        let expected = Canvas::from_lines(
+            top_left,
+            read_lines(snapshot)
+                .expect("Snapshot exists")
+                .map(|line| line.expect("Readable snapshot")),
+        );
+
+        assert_eq!(canvas.top_left, expected.top_left);
+        assert_eq!(canvas.to_string(), expected.to_string());
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    collections::{HashMap, HashSet, LinkedList},
-    fmt::Display,
-};
+use std::{collections::HashMap, fmt::Display};
 
 use iter_tools::Itertools;
 use nom::{
@@ -15,6 +12,10 @@ use nom::{
     IResult,
 };
 
+use crate::bitset::BitSet;
+use crate::memo::Memo;
+use crate::search::floyd_warshall;
+
 #[derive(Debug, Default)]
 struct DirectSpaceGraphNode {
     flow: i32,
@@ -116,31 +117,6 @@ where
     (label_to_id.get(&to_u16("AA")).cloned().expect("😭"), result)
 }
 
-fn all_pairs_shorted(graph: &[SpaceGraphNode]) -> Vec<i32> {
-    let len = graph.len();
-    let mut dist = vec![i32::MAX / 32; graph.len() * graph.len()];
-
-    graph.iter().enumerate().for_each(|(i, node)| {
-        dist[i * len + i] = 0;
-        for j in &node.neighbors {
-            dist[i * len + j] = 1;
-            dist[j * len + i] = 1;
-        }
-    });
-
-    for k in 0..len {
-        for i in 0..len {
-            for j in 0..len {
-                if dist[i * len + j] > dist[i * len + k] + dist[k * len + j] {
-                    dist[i * len + j] = dist[i * len + k] + dist[k * len + j]
-                }
-            }
-        }
-    }
-
-    dist
-}
-
 struct SimplifiedNode {
     orig_id: usize,
     flow: i32,
@@ -152,8 +128,12 @@ fn simplify_graph(
     start: usize,
     graph: Vec<SpaceGraphNode>,
 ) -> (usize, Vec<SimplifiedNode>) {
-    let len = graph.len();
-    let all_pairs = all_pairs_shorted(&graph);
+    let all_pairs = floyd_warshall(graph.len(), &|i: &usize| {
+        graph[*i].neighbors.iter().map(|j| (*j, 1)).collect_vec()
+    });
+    let dist = |i: usize, j: usize| {
+        all_pairs.distance(i, j).expect("All valves are connected")
+    };
 
     let mut result = graph
         .iter()
@@ -175,7 +155,7 @@ fn simplify_graph(
             .filter(|(j, _)| *j != i)
             .map(|(j, n)| {
                 let j_orig = n.orig_id;
-                (all_pairs[i_orig * len + j_orig] + 1, j)
+                (dist(i_orig, j_orig) + 1, j)
             })
             .collect_vec();
         result[i].neighbors = neighbors;
@@ -189,7 +169,7 @@ fn simplify_graph(
             .iter()
             .enumerate()
             .filter(|(i, _)| *i != start)
-            .map(|(i, n)| (all_pairs[start * len + n.orig_id] + 1, i))
+            .map(|(i, n)| (dist(start, n.orig_id) + 1, i))
             .collect_vec(),
     };
 
@@ -198,51 +178,35 @@ fn simplify_graph(
     (result.len() - 1, result)
 }
 
+/// Most pressure that can be released from the given valve with the given
+/// time left and set of valves already opened
 fn search(start: usize, mins: i32, graph: &[SimplifiedNode]) -> i32 {
-    let mut queue = LinkedList::new();
-
-    let mut max = (start, mins, 0, 0_u64, "Root".to_owned());
-
-    queue.push_back(max.clone());
-
-    let mut new_paths = vec![];
+    let mut memo = Memo::new();
 
-    while !queue.is_empty() {
-        let (curr_idx, mins_left, total_flow, state, label) =
-            queue.pop_front().expect("🥸");
-        let curr = &graph[curr_idx];
-
-        new_paths.extend(
-            curr.neighbors
+    memo.call(
+        (start, mins, BitSet::new()),
+        &|(curr, mins_left, opened), recurse| {
+            graph[*curr]
+                .neighbors
                 .iter()
-                .filter(|(d, n)| {
-                    mins_left - d >= 0 && state & (1 << *n as u64) == 0
-                })
+                .filter(|(d, n)| mins_left - d >= 0 && !opened.contains(*n))
                 .map(|(d, n)| {
-                    (
-                        *n,
-                        mins_left - d,
-                        total_flow + graph[*n].flow * (mins_left - d),
-                        state | (1 << *n as u64),
-                        graph[*n].label.clone(),
-                    )
-                }),
-        );
-
-        if new_paths.is_empty() && max.2 < total_flow {
-            max = (curr_idx, mins_left, total_flow, state, label);
-        }
+                    let mut now_opened = opened.clone();
+                    now_opened.insert(*n);
 
-        new_paths.drain(..).for_each(|v| queue.push_back(v));
-    }
-
-    max.2
+                    graph[*n].flow * (mins_left - d)
+                        + recurse((*n, mins_left - d, now_opened))
+                })
+                .max()
+                .unwrap_or(0)
+        },
+    )
 }
 
 fn partition(
     start: usize,
     graph: &[SimplifiedNode],
-    left_nodes: &HashSet<usize>,
+    left_nodes: &BitSet,
 ) -> (Vec<SimplifiedNode>, Vec<SimplifiedNode>) {
     let left = graph
         .iter()
@@ -251,7 +215,7 @@ fn partition(
                 .neighbors
                 .iter()
                 .copied()
-                .filter(|(_, i)| *i == start || left_nodes.contains(i))
+                .filter(|(_, i)| *i == start || left_nodes.contains(*i))
                 .collect_vec();
 
             SimplifiedNode {
@@ -269,7 +233,7 @@ fn partition(
                 .neighbors
                 .iter()
                 .copied()
-                .filter(|(_, i)| *i == start || !left_nodes.contains(i))
+                .filter(|(_, i)| *i == start || !left_nodes.contains(*i))
                 .collect_vec();
 
             SimplifiedNode {
@@ -284,23 +248,6 @@ fn partition(
     (left, right)
 }
 
-fn subsets(set: HashSet<usize>) -> Vec<HashSet<usize>> {
-    let len = set.len();
-    let mut result = Vec::with_capacity(1 << len);
-
-    result.push(HashSet::new());
-
-    for v in set {
-        for i in 0..result.len() {
-            let mut curr = result[i].clone();
-            curr.insert(v);
-            result.push(curr);
-        }
-    }
-
-    result
-}
-
 /**** Problem 1 ******/
 
 pub fn problem_1<I>(input_lines: I) -> impl Display
@@ -324,16 +271,12 @@ where
 
     let (start, graph) = simplify_graph(start, space_graph);
 
-    let subsets = subsets(
-        (0..graph.len())
-            .filter(|i| *i != start)
-            .collect::<HashSet<_>>(),
-    );
+    let valves = (0..graph.len()).filter(|i| *i != start).collect::<BitSet>();
 
     let min_subset_size = (graph.len() - 1) * 40 / 100;
 
-    subsets
-        .into_iter()
+    valves
+        .subsets()
         .filter(|p| {
             p.len() > min_subset_size
                 && p.len() < (graph.len() - min_subset_size)
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use crate::cycle::find_cycle;
+
 const HORIZ: [u16; 4] = [0b000111100, 0, 0, 0];
 const PLUS: [u16; 4] = [0b000010000, 0b000111000, 0b000010000, 0];
 const BEND: [u16; 4] = [0b000111000, 0b000001000, 0b000001000, 0];
@@ -9,15 +11,19 @@ const SQR: [u16; 4] = [0b000110000, 0b000110000, 0, 0];
 const WALLS: u16 = 0b100000001;
 const FLOOR: u16 = 0b111111111;
 
+/// Rows kept in the chamber, which get reused once the pile grows past them.
+/// This is a plain `%` rather than a `topology::Torus`: row numbers go well
+/// past what fits in a `Point2` when `problem_2_slow` runs, and the chamber
+/// only ever looks rows up, it never steps between cells
 const DEPTH: usize = 1_000_000;
-const MIN_REPEAT_START: usize = 100;
-const MAX_REPEAT_START: usize = 100_000;
-const MAX_REPEAT_SIZE: usize = 10_000;
-const REPEAT_SAMPLE_SIZE: usize = 20;
-const REPEAT_VERIFICATION_COUNT: usize = 10;
+/// Rocks to drop while looking for the chamber to start repeating itself
+const MAX_ROCKS_BEFORE_REPEAT: usize = 100_000;
 
-const ROCKS_NEEDED_FOR_REPEATS: usize =
-    MAX_REPEAT_START + REPEAT_VERIFICATION_COUNT * MAX_REPEAT_SIZE;
+/// Rows from the top of the chamber included in its state. This is a
+/// heuristic: nothing stops a rock from slipping further down into the pile,
+/// it just doesn't happen with the puzzle's winds, and anything deeper than the
+/// deepest rock can't change the future. Part 2 checks it as it goes
+const SURFACE_ROWS: usize = 64;
 
 #[derive(Debug, Clone, Copy)]
 enum Delta {
@@ -67,7 +73,7 @@ fn replace(target: &mut [u16], source: &[u16]) {
         .for_each(|(t, s)| *t = *s);
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 enum Rock {
     Horiz,
     Plus,
@@ -186,108 +192,13 @@ impl FallingRock {
     }
 }
 
-fn verify_repeat(repeat_start: usize, repeat_len: usize, dh: &[usize]) -> bool {
-    let to_match = &dh[repeat_start..(repeat_start + REPEAT_SAMPLE_SIZE)];
-
-    for i in 2..REPEAT_VERIFICATION_COUNT {
-        let this_range_start = repeat_start + i * repeat_len;
-        let maybe_match =
-            &dh[this_range_start..(this_range_start + REPEAT_SAMPLE_SIZE)];
-
-        if to_match != maybe_match {
-            return false;
-        }
-    }
-
-    true
-}
-
-#[derive(Debug)]
-struct HeightEquation {
-    preamble: Vec<usize>,
-    rocks_before_repeat: usize,
-    height_before_repeat: usize,
-    repeated_height_deltas: Vec<usize>,
-    repeat_length: usize,
-    total_repeat_height: usize,
-}
-
-impl HeightEquation {
-    fn new(mut wind: WindSource) -> Self {
-        let mut chamber = Chamber::new();
-        let rocks = RockSource::default();
-
-        let mut height_diffs = vec![0_usize; ROCKS_NEEDED_FOR_REPEATS];
-        let mut prev_height = 0;
-
-        height_diffs.iter_mut().zip(rocks).for_each(|(dh, rock)| {
-            let new_height = chamber.apply(&mut wind, rock);
-
-            *dh = new_height - prev_height;
-            prev_height = new_height;
-        });
-
-        let mut repeat_opt: Option<(usize, usize)> = None;
-
-        'outer: for repeat_start in MIN_REPEAT_START..MAX_REPEAT_START {
-            let to_match = &height_diffs
-                [repeat_start..(repeat_start + REPEAT_SAMPLE_SIZE)];
-
-            for repeat_len in 1..MAX_REPEAT_SIZE {
-                let maybe_match = &height_diffs
-                    [repeat_len..(repeat_len + REPEAT_SAMPLE_SIZE)];
-
-                if maybe_match == to_match
-                    && verify_repeat(repeat_start, repeat_len, &height_diffs)
-                {
-                    repeat_opt = Some((repeat_start, repeat_len));
-                    break 'outer;
-                }
-            }
-        }
-
-        let (repeat_start, repeat_length) = repeat_opt.expect("💩");
-
-        HeightEquation {
-            preamble: height_diffs[0..repeat_start].to_owned(),
-            rocks_before_repeat: repeat_start,
-            height_before_repeat: height_diffs[0..repeat_start]
-                .iter()
-                .sum::<usize>(),
-            repeated_height_deltas: height_diffs
-                [repeat_start..(repeat_start + repeat_length)]
-                .to_owned(),
-            repeat_length,
-            total_repeat_height: height_diffs
-                [repeat_start..(repeat_start + repeat_length)]
-                .iter()
-                .sum(),
-        }
-    }
-
-    fn eval(&self, rock_count: usize) -> usize {
-        if rock_count <= self.rocks_before_repeat {
-            self.preamble.iter().take(rock_count).sum::<usize>()
-        } else {
-            let after_preamble = rock_count - self.rocks_before_repeat;
-            let complete_repeats = after_preamble / self.repeat_length;
-            let rocks_after_repeats = after_preamble % self.repeat_length;
-
-            self.height_before_repeat
-                + complete_repeats * self.total_repeat_height
-                + self
-                    .repeated_height_deltas
-                    .iter()
-                    .take(rocks_after_repeats)
-                    .sum::<usize>()
-        }
-    }
-}
-
 struct Chamber {
     board: Vec<u16>,
     height: usize,
     len: usize,
+
+    /// Furthest any rock has come to rest below the top of the pile
+    deepest: usize,
 }
 
 impl Chamber {
@@ -296,6 +207,7 @@ impl Chamber {
             board: vec![0; DEPTH],
             height: 1,
             len: 1,
+            deepest: 0,
         };
 
         result.board[0] = FLOOR;
@@ -333,6 +245,8 @@ impl Chamber {
             self.board[(rock.bottom + i) % DEPTH] |= rock.space[i];
         }
 
+        self.deepest =
+            self.deepest.max(self.height.saturating_sub(rock.bottom));
         self.height = self.height.max(rock.bottom + rock.lines);
 
         self.height - 1
@@ -364,6 +278,17 @@ impl Chamber {
         self.finalize(rock)
     }
 
+    /// Top few rows of the pile, which along with the next rock and the
+    /// position in the wind is all that decides what happens next
+    fn surface(&self) -> [u16; SURFACE_ROWS] {
+        std::array::from_fn(|i| {
+            self.height
+                .checked_sub(i + 1)
+                .map(|row| self.board[row % DEPTH])
+                .unwrap_or(FLOOR)
+        })
+    }
+
     fn get_height_after_block_count(block_number: usize) {}
 }
 
@@ -389,11 +314,28 @@ pub fn problem_2<I>(mut input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let wind = WindSource::new(input_lines.next().expect("😱"));
-
-    let equation = HeightEquation::new(wind);
-
-    equation.eval(1_000_000_000_000)
+    let mut wind = WindSource::new(input_lines.next().expect("😱"));
+    let mut chamber = Chamber::new();
+    let mut rocks = RockSource::default();
+
+    let cycle = find_cycle(0, MAX_ROCKS_BEFORE_REPEAT, |_| {
+        let rock = rocks.next().expect("🪨");
+        let height = chamber.apply(&mut wind, rock);
+        let state = (rock, wind.cursor % wind.line.len(), chamber.surface());
+
+        // Otherwise matching states might not have matching futures
+        assert!(
+            chamber.deepest < SURFACE_ROWS,
+            "A rock fell {} rows into the pile, past the {SURFACE_ROWS} rows \
+             kept in the state",
+            chamber.deepest
+        );
+
+        (state, height as i64)
+    })
+    .expect("Rocks should fall into a pattern eventually");
+
+    cycle.extrapolate(1_000_000_000_000)
 }
 
 pub fn problem_2_slow<I>(mut input_lines: I) -> impl Display
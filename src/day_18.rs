@@ -1,125 +1,55 @@
 use std::{collections::HashSet, fmt::Display};
 
 use iter_tools::Itertools;
-use nom::{
-    character::complete::{char, i32},
-    sequence::{terminated, tuple},
-    IResult,
-};
 
-fn parse_voxel(line: String) -> (i32, i32, i32) {
-    let result: IResult<&str, (i32, i32, i32)> =
-        tuple((terminated(i32, char(',')), terminated(i32, char(',')), i32))(
-            &line,
-        );
+use crate::components::Components;
+use crate::geometry::Point3;
+use crate::parsing::ParseLines;
 
-    result.expect("🫥").1
+fn parse_voxels<I>(lines: I) -> impl Iterator<Item = Point3>
+where
+    I: Iterator<Item = String>,
+{
+    lines
+        .parse_lines::<Point3>()
+        .map(|voxel| voxel.expect("🫥"))
 }
 
-fn all_connected(
-    min: (i32, i32, i32),
-    max: (i32, i32, i32),
-    voxels: &HashSet<(i32, i32, i32)>,
-    exterior: &HashSet<(i32, i32, i32)>,
-    start: (i32, i32, i32),
-) -> (Vec<(i32, i32, i32)>, bool) {
-    let mut visited = HashSet::new();
-    let mut stack = vec![start];
-    let mut ext = false;
-
-    while !stack.is_empty() {
-        let p = stack.pop().expect("💀");
-
-        visited.insert(p);
-        if exterior.contains(&p) {
-            ext = true
-        }
+/// Number of faces of the given voxels that aren't shared with another one
+fn surface_area<I>(voxels: I) -> i32
+where
+    I: IntoIterator<Item = Point3>,
+{
+    let mut seen = HashSet::new();
 
-        stack.extend(
-            [
-                (p.0 + 1, p.1, p.2),
-                (p.0 - 1, p.1, p.2),
-                (p.0, p.1 + 1, p.2),
-                (p.0, p.1 - 1, p.2),
-                (p.0, p.1, p.2 + 1),
-                (p.0, p.1, p.2 - 1),
-            ]
-            .iter()
-            .filter(|n| voxels.contains(n))
-            .filter(|n| !visited.contains(*n)),
-        );
-    }
+    voxels
+        .into_iter()
+        .map(|p| {
+            seen.insert(p);
+            let neighbors = p.neighbors6().filter(|v| seen.contains(v)).count();
 
-    (visited.into_iter().collect_vec(), ext)
+            6 - 2 * neighbors as i32
+        })
+        .sum()
 }
 
+/// Surface area of the pockets of air trapped inside the droplet
 fn find_interior_space_surface(
-    min: (i32, i32, i32),
-    max: (i32, i32, i32),
-    voxels: HashSet<(i32, i32, i32)>,
+    min: Point3,
+    max: Point3,
+    voxels: &HashSet<Point3>,
 ) -> i32 {
-    let mut non_voxels = HashSet::new();
-    let mut exterior = HashSet::new();
-
-    for x in min.0..=max.0 {
-        for y in min.1..=max.1 {
-            for z in min.2..=max.2 {
-                let p = (x, y, z);
-
-                if !voxels.contains(&p) {
-                    let ext = x == min.0
-                        || y == min.1
-                        || z == min.2
-                        || x == max.0
-                        || y == max.1
-                        || z == max.2;
-                    non_voxels.insert(p);
-                    if ext {
-                        exterior.insert(p);
-                    }
-                }
-            }
-        }
-    }
-
-    let mut total_surface_area = 0;
-
-    while !non_voxels.is_empty() {
-        let curr = *non_voxels.iter().next().expect("🤠");
-
-        let (all_connected, ext) =
-            all_connected(min, max, &non_voxels, &exterior, curr);
-
-        all_connected.iter().for_each(|v| {
-            non_voxels.remove(v);
-        });
-
-        if !ext {
-            let mut anti_voxels = HashSet::new();
-            let mut surface_area = 0_i32;
-
-            for p in all_connected.into_iter() {
-                anti_voxels.insert(p);
-                let neighbors = [
-                    (p.0 + 1, p.1, p.2),
-                    (p.0 - 1, p.1, p.2),
-                    (p.0, p.1 + 1, p.2),
-                    (p.0, p.1 - 1, p.2),
-                    (p.0, p.1, p.2 + 1),
-                    (p.0, p.1, p.2 - 1),
-                ]
-                .into_iter()
-                .filter(|v| anti_voxels.contains(v))
-                .count();
-
-                surface_area += 6 - 2 * neighbors as i32;
-            }
-
-            total_surface_area += surface_area;
-        }
-    }
-
-    total_surface_area
+    let air = (min.x..=max.x)
+        .cartesian_product(min.y..=max.y)
+        .cartesian_product(min.z..=max.z)
+        .map(|((x, y), z)| Point3::new(x, y, z))
+        .filter(|p| !voxels.contains(p));
+
+    Components::label(air, min, max)
+        .iter()
+        .filter(|pocket| !pocket.touches_boundary)
+        .map(|pocket| surface_area(pocket.cells.iter().copied()))
+        .sum()
 }
 
 /**** Problem 1 ******/
@@ -128,27 +58,7 @@ pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut voxels = HashSet::new();
-    let mut surface_area = 0_i32;
-
-    for p in input_lines.map(parse_voxel) {
-        voxels.insert(p);
-        let neighbors = [
-            (p.0 + 1, p.1, p.2),
-            (p.0 - 1, p.1, p.2),
-            (p.0, p.1 + 1, p.2),
-            (p.0, p.1 - 1, p.2),
-            (p.0, p.1, p.2 + 1),
-            (p.0, p.1, p.2 - 1),
-        ]
-        .into_iter()
-        .filter(|v| voxels.contains(v))
-        .count();
-
-        surface_area += 6 - 2 * neighbors as i32;
-    }
-
-    surface_area
+    surface_area(parse_voxels(input_lines))
 }
 
 /**** Problem 2 ******/
@@ -157,30 +67,16 @@ pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut voxels = HashSet::new();
-    let mut surface_area = 0_i32;
-    let mut max = (i32::MIN, i32::MIN, i32::MIN);
-    let mut min = (i32::MAX, i32::MAX, i32::MAX);
-
-    for p in input_lines.map(parse_voxel) {
-        min = (min.0.min(p.0), min.1.min(p.1), min.2.min(p.2));
-        max = (max.0.max(p.0), max.1.max(p.1), max.2.max(p.2));
-
-        voxels.insert(p);
-        let neighbors = [
-            (p.0 + 1, p.1, p.2),
-            (p.0 - 1, p.1, p.2),
-            (p.0, p.1 + 1, p.2),
-            (p.0, p.1 - 1, p.2),
-            (p.0, p.1, p.2 + 1),
-            (p.0, p.1, p.2 - 1),
-        ]
-        .into_iter()
-        .filter(|v| voxels.contains(v))
-        .count();
-
-        surface_area += 6 - 2 * neighbors as i32;
-    }
-
-    surface_area - find_interior_space_surface(min, max, voxels)
+    let voxels: HashSet<Point3> = parse_voxels(input_lines).collect();
+
+    let (min, max) = voxels.iter().fold(
+        (
+            Point3::new(i32::MAX, i32::MAX, i32::MAX),
+            Point3::new(i32::MIN, i32::MIN, i32::MIN),
+        ),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+
+    surface_area(voxels.iter().copied())
+        - find_interior_space_surface(min, max, &voxels)
 }
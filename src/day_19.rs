@@ -1,4 +1,3 @@
-use rand::{rngs::ThreadRng, thread_rng, Rng};
 use std::fmt::Display;
 
 use nom::{
@@ -9,11 +8,9 @@ use nom::{
     IResult,
 };
 
-const MAX_RAND: u32 = 1_000_000;
+use crate::memo::Memo;
 
-const SAMPLES: usize = 1000000;
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Material {
     Ore,
     Clay,
@@ -73,167 +70,95 @@ fn parse_blueprint(line: String) -> Blueprint {
     result.expect("🤡").1
 }
 
-#[derive(Debug, Default)]
-struct Inventory {
-    ore_bots: i32,
-    ore: i32,
-    clay_bots: i32,
-    clay: i32,
-    obsidian_bots: i32,
-    obsidian: i32,
-    geode_bots: i32,
-    geodes: i32,
-}
-
-impl Inventory {
-    fn can_buy_ore_bot(&self, bp: &Blueprint) -> bool {
-        self.ore >= bp.ore_bot_cost
-    }
-
-    fn can_buy_clay_bot(&self, bp: &Blueprint) -> bool {
-        self.ore >= bp.clay_bot_cost
-    }
-
-    fn can_buy_obsidian_bot(&self, bp: &Blueprint) -> bool {
-        self.ore >= bp.obsidian_bot_cost.0
-            && self.clay >= bp.obsidian_bot_cost.1
-    }
-
-    fn can_buy_geode_bot(&self, bp: &Blueprint) -> bool {
-        self.ore >= bp.geode_bot_cost.0 && self.obsidian >= bp.geode_bot_cost.1
-    }
-}
-
-trait Strategy {
-    fn buy_ore_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool;
-
-    fn buy_clay_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool;
-
-    fn buy_obsidian_bot(
-        &mut self,
-        bp: &Blueprint,
-        inventory: &Inventory,
-    ) -> bool;
+impl Blueprint {
+    /// Ore, clay, and obsidian needed to build a bot for the given material
+    fn cost(&self, bot: Material) -> Resources {
+        use Material::*;
 
-    fn buy_geode_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool;
-}
-
-/// I had no idea how to solve this problem in a real way, so I tried a
-/// stochastic approach (that worked somehow). Each time a decision needs to be
-/// made, we sample a random number and compare it to a threshold specific to
-/// the decision being made. Running this a bunch of times reliably produces the
-/// optimal results ... yay?
-#[derive(Debug)]
-struct RandomStrategy {
-    rng: ThreadRng,
-    thresholds: (u32, u32, u32, u32),
-}
-
-impl Strategy for RandomStrategy {
-    fn buy_ore_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool {
-        self.rng.gen_range(0..=MAX_RAND) > self.thresholds.0
-    }
-
-    fn buy_clay_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool {
-        self.rng.gen_range(0..=MAX_RAND) > self.thresholds.1
-    }
-
-    fn buy_obsidian_bot(
-        &mut self,
-        bp: &Blueprint,
-        inventory: &Inventory,
-    ) -> bool {
-        self.rng.gen_range(0..=MAX_RAND) > self.thresholds.2
-    }
-
-    fn buy_geode_bot(&mut self, bp: &Blueprint, inventory: &Inventory) -> bool {
-        self.rng.gen_range(0..=MAX_RAND) > self.thresholds.3
-    }
-}
-
-fn eval_blueprints<S>(bp: &Blueprint, strategy: &mut S, minutes: i32) -> i32
-where
-    S: Strategy,
-{
-    let mut inventory = Inventory {
-        ore_bots: 1,
-        ..Inventory::default()
-    };
-
-    for _min in 1..=minutes {
-        let mut news = (0, 0, 0, 0);
-        if inventory.can_buy_geode_bot(bp)
-            && strategy.buy_geode_bot(bp, &inventory)
-        {
-            news.3 = 1;
-            inventory.ore -= bp.geode_bot_cost.0;
-            inventory.obsidian -= bp.geode_bot_cost.1;
-        } else if inventory.can_buy_obsidian_bot(bp)
-            && strategy.buy_obsidian_bot(bp, &inventory)
-        {
-            news.2 = 1;
-            inventory.ore -= bp.obsidian_bot_cost.0;
-            inventory.clay -= bp.obsidian_bot_cost.1;
-        } else if inventory.can_buy_clay_bot(bp)
-            && strategy.buy_clay_bot(bp, &inventory)
-        {
-            news.1 = 1;
-            inventory.ore -= bp.clay_bot_cost;
-        } else if inventory.can_buy_ore_bot(bp)
-            && strategy.buy_ore_bot(bp, &inventory)
-        {
-            news.0 = 1;
-            inventory.ore -= bp.ore_bot_cost;
+        match bot {
+            Ore => [self.ore_bot_cost, 0, 0],
+            Clay => [self.clay_bot_cost, 0, 0],
+            Obsidian => [self.obsidian_bot_cost.0, self.obsidian_bot_cost.1, 0],
+            Geode => [self.geode_bot_cost.0, 0, self.geode_bot_cost.1],
         }
-
-        inventory.ore += inventory.ore_bots;
-        inventory.clay += inventory.clay_bots;
-        inventory.obsidian += inventory.obsidian_bots;
-        inventory.geodes += inventory.geode_bots;
-
-        inventory.ore_bots += news.0;
-        inventory.clay_bots += news.1;
-        inventory.obsidian_bots += news.2;
-        inventory.geode_bots += news.3;
     }
 
-    inventory.geodes
-}
-
-fn run_random_averaged_step<S>(
-    bp: &Blueprint,
-    strategy: &mut S,
-    minutes: i32,
-) -> (i32, f64)
-where
-    S: Strategy,
-{
-    let mut strategy = RandomStrategy {
-        rng: thread_rng(),
-        thresholds: (MAX_RAND >> 1, MAX_RAND >> 1, MAX_RAND >> 3, 0),
-    };
-
-    let mut avg_geodes = 0.0;
-    let mut max: i32 = 0;
-
-    for _ in 0..SAMPLES {
-        let result = eval_blueprints(bp, &mut strategy, minutes);
-        avg_geodes += result as f64 / SAMPLES as f64;
-        max = max.max(result);
+    /// Most of each resource that can be spent in a single minute. Having more
+    /// bots than this for a resource can't help because only one bot can be
+    /// built per minute
+    fn max_spend(&self) -> Resources {
+        [
+            self.ore_bot_cost
+                .max(self.clay_bot_cost)
+                .max(self.obsidian_bot_cost.0)
+                .max(self.geode_bot_cost.0),
+            self.obsidian_bot_cost.1,
+            self.geode_bot_cost.1,
+        ]
     }
-
-    (max, avg_geodes)
 }
 
-fn run_random_strategy(bp: Blueprint, minutes: i32) -> i32 {
-    let mut strategy = RandomStrategy {
-        rng: thread_rng(),
-        thresholds: (MAX_RAND >> 1, MAX_RAND >> 1, 0, 0),
+/// Amounts of ore, clay, and obsidian. Geodes are never spent, so they are
+/// counted up front as soon as a geode bot is built instead of tracked here
+type Resources = [i32; 3];
+
+/// Minutes left paired with the bots and resources on hand
+type State = (i32, Resources, Resources);
+
+/// Most geodes that can still be cracked from the given state. Rather than
+/// stepping minute by minute, each branch picks the next bot to build and
+/// skips straight to the minute it's done
+fn most_geodes(bp: &Blueprint, minutes: i32) -> i32 {
+    use Material::*;
+
+    let max_spend = bp.max_spend();
+    let mut memo = Memo::new();
+
+    let search = |(time_left, bots, resources): &State,
+                  recurse: &mut dyn FnMut(State) -> i32| {
+        [Geode, Obsidian, Clay, Ore]
+            .into_iter()
+            .filter(|bot| {
+                *bot == Geode || bots[*bot as usize] < max_spend[*bot as usize]
+            })
+            .filter_map(|bot| {
+                let cost = bp.cost(bot);
+
+                // Minutes spent collecting before the bot can be started
+                let wait = (0..3)
+                    .filter(|r| cost[*r] > resources[*r])
+                    .map(|r| {
+                        (bots[r] > 0).then(|| {
+                            (cost[r] - resources[r] + bots[r] - 1) / bots[r]
+                        })
+                    })
+                    .try_fold(0, |wait, w| w.map(|w| wait.max(w)))?;
+
+                let time_left = time_left - wait - 1;
+                if time_left <= 0 {
+                    return None;
+                }
+
+                let mut next_bots = *bots;
+                let next_resources = std::array::from_fn(|r| {
+                    let amount = resources[r] + bots[r] * (wait + 1) - cost[r];
+                    amount.min(max_spend[r] * time_left)
+                });
+
+                let geodes = if bot == Geode {
+                    time_left
+                } else {
+                    next_bots[bot as usize] += 1;
+                    0
+                };
+
+                Some(geodes + recurse((time_left, next_bots, next_resources)))
+            })
+            .max()
+            .unwrap_or(0)
     };
 
-    let result = run_random_averaged_step(&bp, &mut strategy, minutes);
-
-    result.0
+    memo.call((minutes, [1, 0, 0], [0, 0, 0]), &search)
 }
 
 /**** Problem 1 ******/
@@ -244,7 +169,7 @@ where
 {
     input_lines
         .map(parse_blueprint)
-        .map(|bp| run_random_strategy(bp, 24))
+        .map(|bp| most_geodes(&bp, 24))
         .enumerate()
         .map(|(i, o)| (i as i32 + 1) * o)
         .sum::<i32>()
@@ -259,6 +184,6 @@ where
     input_lines
         .map(parse_blueprint)
         .take(3)
-        .map(|bp| run_random_strategy(bp, 32))
+        .map(|bp| most_geodes(&bp, 32))
         .product::<i32>()
 }
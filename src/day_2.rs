@@ -1,59 +1,543 @@
+#![allow(clippy::upper_case_acronyms)]
+
 use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone)]
-#[repr(i32)]
-enum RPS {
-    R = 1,
-    P = 2,
-    S = 3,
-}
+use iter_tools::Itertools;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, char, i32, satisfy},
+    combinator::{map, opt, rest, value},
+    sequence::{preceded, tuple},
+    IResult,
+};
+
+use crate::helpers::{is_report, read_lines, rules_file};
+use crate::parsing::{LineError, ParseLines};
+
+/// Plain old rock paper scissors with the letters from the puzzle
+const STANDARD_RULES: &str = "\
+move rock 1 A X
+move paper 2 B Y
+move scissors 3 C Z
+beats rock scissors
+beats paper rock
+beats scissors paper
+outcome lose 0 X
+outcome draw 3 Y
+outcome win 6 Z";
 
-#[derive(Debug, Copy, Clone)]
-#[repr(i32)]
+/// A move in the game, as its position in the rules' list of moves
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct RPS(usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(usize)]
 enum LDW {
-    L = 0,
-    D = 3,
-    W = 6,
+    L,
+    D,
+    W,
 }
 
-fn to_rps(i: u8) -> RPS {
-    use RPS::*;
-    match i {
-        b'A' | b'X' => R,
-        b'B' | b'Y' => P,
-        b'C' | b'Z' => S,
-        _ => unreachable!("Input guaranteed to be valid"),
-    }
+impl LDW {
+    const ALL: [LDW; 3] = [LDW::L, LDW::D, LDW::W];
+}
+
+/// One line of a rules spec
+#[derive(Debug, Clone)]
+enum Entry {
+    Move {
+        name: String,
+        score: i32,
+        theirs: u8,
+        mine: u8,
+    },
+    Beats(String, String),
+    Outcome {
+        outcome: LDW,
+        score: i32,
+        letter: u8,
+    },
+    Blank,
+}
+
+fn parse_letter(input: &str) -> IResult<&str, u8> {
+    map(satisfy(|c| c.is_ascii_graphic()), |c| c as u8)(input)
+}
+
+fn parse_name(input: &str) -> IResult<&str, String> {
+    map(alpha1, str::to_owned)(input)
+}
+
+fn parse_entry(input: &str) -> IResult<&str, Entry> {
+    alt((
+        map(
+            tuple((
+                preceded(tag("move "), parse_name),
+                preceded(char(' '), i32),
+                preceded(char(' '), parse_letter),
+                preceded(char(' '), parse_letter),
+            )),
+            |(name, score, theirs, mine)| Entry::Move {
+                name,
+                score,
+                theirs,
+                mine,
+            },
+        ),
+        map(
+            tuple((
+                preceded(tag("beats "), parse_name),
+                preceded(char(' '), parse_name),
+            )),
+            |(winner, loser)| Entry::Beats(winner, loser),
+        ),
+        map(
+            tuple((
+                preceded(
+                    tag("outcome "),
+                    alt((
+                        value(LDW::L, tag("lose")),
+                        value(LDW::D, tag("draw")),
+                        value(LDW::W, tag("win")),
+                    )),
+                ),
+                preceded(char(' '), i32),
+                preceded(char(' '), parse_letter),
+            )),
+            |(outcome, score, letter)| Entry::Outcome {
+                outcome,
+                score,
+                letter,
+            },
+        ),
+        value(Entry::Blank, opt(preceded(char('#'), rest))),
+    ))(input)
 }
 
-fn to_ldw(i: u8) -> LDW {
-    use LDW::*;
-    match i {
-        b'X' => L,
-        b'Y' => D,
-        b'Z' => W,
-        _ => unreachable!("Input guaranteed to be valid"),
+#[derive(Debug)]
+struct Move {
+    name: String,
+
+    /// Points for playing this move
+    score: i32,
+
+    /// Letters standing for this move in the opponent's column and in mine
+    theirs: u8,
+    mine: u8,
+}
+
+/// Everything needed to score a game: the moves, who beats whom, and what
+/// each outcome is worth. Both ways of reading the strategy guide come down
+/// to lookups in the tables built from these
+#[derive(Debug)]
+struct Rules {
+    moves: Vec<Move>,
+
+    /// Letter and points for each outcome, indexed by outcome
+    outcomes: [(u8, i32); 3],
+
+    /// How things turn out for me, indexed by their move then mine
+    results: Vec<Vec<LDW>>,
+
+    /// My total score, indexed by their move then mine
+    payoff: Vec<Vec<i32>>,
+
+    /// First move (in the order they're listed) that gets each outcome
+    /// against each of their moves
+    responses: Vec<[RPS; 3]>,
+}
+
+impl Rules {
+    /// Regular rock paper scissors, unless a rules file was passed with
+    /// `--rules`
+    fn load() -> Self {
+        let rules = match rules_file() {
+            Some(path) => Rules::parse(
+                read_lines(path)
+                    .unwrap_or_else(|e| panic!("Can't read day 2 rules, {e}"))
+                    .map(|l| l.expect("📜")),
+            ),
+            None => Rules::standard(),
+        };
+
+        rules.unwrap_or_else(|e| panic!("Bad rules for day 2, {e}"))
+    }
+
+    fn standard() -> Result<Self, LineError> {
+        Rules::parse(STANDARD_RULES.lines().map(str::to_owned))
+    }
+
+    /// Read a rules spec. Each line is one of
+    ///
+    /// - `move <name> <points> <their letter> <my letter>`
+    /// - `beats <winner> <loser>`
+    /// - `outcome <lose|draw|win> <points> <letter>`
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Moves that don't
+    /// beat each other either way are a draw
+    fn parse<I>(lines: I) -> Result<Self, LineError>
+    where
+        I: Iterator<Item = String>,
+    {
+        let lines = lines.collect_vec();
+        let entries = lines
+            .iter()
+            .cloned()
+            .parse_lines_with(parse_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let complaint = |line: usize, reason: String| LineError {
+            line: line + 1,
+            column: 1,
+            text: lines.get(line).cloned().unwrap_or_default(),
+            reason,
+        };
+
+        let mut moves: Vec<Move> = vec![];
+        let mut outcomes = [None; 3];
+        let mut beats = vec![];
+
+        for (line, entry) in entries.into_iter().enumerate() {
+            match entry {
+                Entry::Move {
+                    name,
+                    score,
+                    theirs,
+                    mine,
+                } => {
+                    if moves.iter().any(|m| {
+                        m.name == name || m.theirs == theirs || m.mine == mine
+                    }) {
+                        return Err(complaint(line, "Duplicate move".into()));
+                    }
+                    moves.push(Move {
+                        name,
+                        score,
+                        theirs,
+                        mine,
+                    });
+                }
+                Entry::Beats(winner, loser) => {
+                    beats.push((line, winner, loser))
+                }
+                Entry::Outcome {
+                    outcome,
+                    score,
+                    letter,
+                } => outcomes[outcome as usize] = Some((letter, score)),
+                Entry::Blank => {}
+            }
+        }
+
+        // Problems with the rules as a whole go on the last line
+        let end = lines.len().saturating_sub(1);
+        let outcomes = match outcomes {
+            [Some(l), Some(d), Some(w)] => [l, d, w],
+            _ => {
+                return Err(complaint(end, "Missing an outcome".into()));
+            }
+        };
+
+        let find = |line: usize, name: &str| {
+            moves
+                .iter()
+                .position(|m| m.name == name)
+                .ok_or_else(|| complaint(line, format!("Unknown move {name}")))
+        };
+
+        let mut results = vec![vec![LDW::D; moves.len()]; moves.len()];
+        for (line, winner, loser) in beats {
+            let (winner, loser) = (find(line, &winner)?, find(line, &loser)?);
+            if winner == loser || results[winner][loser] == LDW::W {
+                return Err(complaint(line, "Contradictory rule".into()));
+            }
+
+            results[loser][winner] = LDW::W;
+            results[winner][loser] = LDW::L;
+        }
+
+        let payoff = results
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&moves)
+                    .map(|(result, mine)| {
+                        outcomes[*result as usize].1 + mine.score
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let mut responses = vec![[RPS(0); 3]; moves.len()];
+        for (theirs, row) in results.iter().enumerate() {
+            for outcome in LDW::ALL {
+                let Some(mine) = row.iter().position(|r| *r == outcome) else {
+                    let name = &moves[theirs].name;
+                    let reason = format!("No move gets {outcome:?} vs {name}");
+                    return Err(complaint(end, reason));
+                };
+                responses[theirs][outcome as usize] = RPS(mine);
+            }
+        }
+
+        Ok(Self {
+            moves,
+            outcomes,
+            results,
+            payoff,
+            responses,
+        })
+    }
+
+    /// Move for a letter in the opponent's column
+    fn theirs(&self, letter: u8) -> RPS {
+        RPS(self
+            .moves
+            .iter()
+            .position(|m| m.theirs == letter)
+            .expect("🤷"))
+    }
+
+    /// Move for a letter in my column
+    fn mine(&self, letter: u8) -> RPS {
+        RPS(self
+            .moves
+            .iter()
+            .position(|m| m.mine == letter)
+            .expect("🤷"))
+    }
+
+    /// Outcome for a letter in my column
+    fn to_ldw(&self, letter: u8) -> LDW {
+        LDW::ALL
+            .into_iter()
+            .find(|o| self.outcomes[*o as usize].0 == letter)
+            .expect("🤷")
+    }
+
+    fn score_p1(&self, (other, mine): (RPS, RPS)) -> i32 {
+        self.payoff[other.0][mine.0]
+    }
+
+    fn score_p2(&self, (other, target): (RPS, LDW)) -> i32 {
+        self.score_p1((other, self.responses[other.0][target as usize]))
+    }
+
+    /// Total for a strategy guide read the problem 1 way
+    fn score_guide<I>(&self, guide: I) -> i32
+    where
+        I: Iterator<Item = String>,
+    {
+        guide
+            .map(|line| line.into_bytes())
+            .map(|bytes| (self.theirs(bytes[0]), self.mine(bytes[2])))
+            .map(|round| self.score_p1(round))
+            .sum()
     }
 }
 
-impl RPS {
-    fn score_p1((other, mine): (Self, Self)) -> i32 {
-        use RPS::*;
-        match (other, mine) {
-            (R, S) | (P, R) | (S, P) => mine as i32, // Lose
-            (R, R) | (P, P) | (S, S) => 3 + mine as i32, // Draw
-            (R, P) | (P, S) | (S, R) => 6 + mine as i32, // Win
+/// Solve `a x = b` for square `a`, or nothing if `a` is singular
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col] / pivot_row[col];
+                for (x, p) in a[row].iter_mut().zip(&pivot_row).skip(col) {
+                    *x -= factor * p;
+                }
+                b[row] -= factor * b[col];
+            }
         }
     }
 
-    fn score_p2((other, target): (Self, LDW)) -> i32 {
-        use LDW::*;
-        use RPS::*;
-        match (other, target) {
-            (R, D) | (P, L) | (S, W) => 1 + target as i32, // Rock
-            (R, W) | (P, D) | (S, L) => 2 + target as i32, // Paper
-            (R, L) | (P, W) | (S, D) => 3 + target as i32, // Scissors
+    Some((0..n).map(|i| b[i] / a[i][i]).collect())
+}
+
+/// What the best plays would have been for a strategy guide, and how the
+/// guide stacks up against them
+#[derive(Debug)]
+struct Plan {
+    /// Names of the moves, for reporting
+    names: Vec<String>,
+
+    /// How often each of their moves shows up in the guide
+    distribution: Vec<f64>,
+
+    /// Score for the guide read the problem 1 way
+    guide_score: i32,
+
+    /// Move each of my letters should have meant, in the order the moves are
+    /// listed, along with what that would have scored
+    best_mapping: Vec<RPS>,
+    best_mapping_score: i32,
+
+    /// Score for making the best possible move every single round
+    best_response_score: i32,
+
+    /// How often to play each move to get the best expected score per round
+    /// no matter what they throw, and what that score is
+    mixed: Vec<f64>,
+    mixed_value: f64,
+}
+
+impl Plan {
+    /// Points the guide leaves on the table compared to the best reading of
+    /// my column
+    fn shortfall(&self) -> i32 {
+        self.best_mapping_score - self.guide_score
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percents = |ps: &[f64]| {
+            ps.iter()
+                .zip(&self.names)
+                .map(|(p, name)| format!("{name} {:.1}%", p * 100.))
+                .join(", ")
+        };
+
+        writeln!(f, "Their moves: {}", percents(&self.distribution))?;
+        writeln!(f, "Guide as written: {}", self.guide_score)?;
+        writeln!(
+            f,
+            "Best reading of my column: {} ({:?}, {} short)",
+            self.best_mapping_score,
+            self.best_mapping
+                .iter()
+                .map(|m| &self.names[m.0])
+                .collect_vec(),
+            self.shortfall()
+        )?;
+        writeln!(f, "Best move every round: {}", self.best_response_score)?;
+        write!(
+            f,
+            "Best mixed strategy: {} for {:.3} a round",
+            percents(&self.mixed),
+            self.mixed_value
+        )
+    }
+}
+
+impl Rules {
+    /// Work out how the given strategy guide could have gone better
+    fn plan<I>(&self, guide: I) -> Plan
+    where
+        I: Iterator<Item = String>,
+    {
+        let n = self.moves.len();
+
+        // Number of rounds for each of their moves paired with each of mine
+        let mut counts = vec![vec![0; n]; n];
+        for bytes in guide.map(String::into_bytes) {
+            counts[self.theirs(bytes[0]).0][self.mine(bytes[2]).0] += 1;
         }
+
+        let rounds = counts.iter().flatten().sum::<i32>().max(1);
+        let distribution = counts
+            .iter()
+            .map(|row| row.iter().sum::<i32>() as f64 / rounds as f64)
+            .collect_vec();
+
+        let score_mapping = |mapping: &[usize]| {
+            (0..n)
+                .cartesian_product(0..n)
+                .map(|(t, i)| counts[t][i] * self.payoff[t][mapping[i]])
+                .sum::<i32>()
+        };
+
+        let guide_score = score_mapping(&(0..n).collect_vec());
+        let (best_mapping_score, best_mapping) = (0..n)
+            .permutations(n)
+            .map(|mapping| (score_mapping(&mapping), mapping))
+            .max_by_key(|(score, _)| *score)
+            .expect("🪢");
+
+        let best_response_score = (0..n)
+            .map(|t| {
+                counts[t].iter().sum::<i32>()
+                    * self.payoff[t].iter().max().expect("🥊")
+            })
+            .sum();
+
+        let (mixed, mixed_value) = self.maximin();
+
+        Plan {
+            names: self.moves.iter().map(|m| m.name.clone()).collect(),
+            distribution,
+            guide_score,
+            best_mapping: best_mapping.into_iter().map(RPS).collect(),
+            best_mapping_score,
+            best_response_score,
+            mixed,
+            mixed_value,
+        }
+    }
+
+    /// Mix of moves with the highest expected score against their worst
+    /// choice of move. This is a small linear program, so every corner of the
+    /// feasible region is tried: pick which inequalities are tight, solve for
+    /// the mix, and keep the best one that doesn't break the others
+    fn maximin(&self) -> (Vec<f64>, f64) {
+        let n = self.moves.len();
+
+        // Variables are the chance of playing each move followed by the value
+        // of the game. Rows are `row . vars >= 0` for each move of theirs and
+        // then each chance
+        let inequalities = (0..n)
+            .map(|t| {
+                let mut row =
+                    self.payoff[t].iter().map(|p| *p as f64).collect_vec();
+                row.push(-1.);
+                row
+            })
+            .chain((0..n).map(|m| {
+                let mut row = vec![0.; n + 1];
+                row[m] = 1.;
+                row
+            }))
+            .collect_vec();
+
+        let mut total = vec![1.; n];
+        total.push(0.);
+
+        (0..inequalities.len())
+            .combinations(n)
+            .filter_map(|tight| {
+                let a = tight
+                    .iter()
+                    .map(|i| inequalities[*i].clone())
+                    .chain(Some(total.clone()))
+                    .collect_vec();
+                let mut b = vec![0.; n];
+                b.push(1.);
+
+                solve_linear(a, b)
+            })
+            .filter(|vars| {
+                inequalities.iter().all(|row| {
+                    row.iter().zip(vars).map(|(a, x)| a * x).sum::<f64>()
+                        > -1e-9
+                })
+            })
+            .map(|mut vars| {
+                let value = vars.pop().expect("🎲");
+                (vars, value)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("Some mix is always feasible")
     }
 }
 
@@ -61,11 +545,19 @@ pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    input_lines
-        .map(|line| line.into_bytes())
-        .map(|bytes| (to_rps(bytes[0]), to_rps(bytes[2])))
-        .map(RPS::score_p1)
-        .sum::<i32>()
+    let rules = Rules::load();
+
+    // Only the report needs the guide read twice
+    if is_report() {
+        let input_lines = input_lines.collect_vec();
+
+        println!();
+        println!("{}", rules.plan(input_lines.iter().cloned()));
+
+        rules.score_guide(input_lines.into_iter())
+    } else {
+        rules.score_guide(input_lines)
+    }
 }
 
 /**** Problem 2 ******/
@@ -74,9 +566,79 @@ pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
+    let rules = Rules::load();
+
     input_lines
         .map(|line| line.into_bytes())
-        .map(|bytes| (to_rps(bytes[0]), to_ldw(bytes[2])))
-        .map(RPS::score_p2)
+        .map(|bytes| (rules.theirs(bytes[0]), rules.to_ldw(bytes[2])))
+        .map(|round| rules.score_p2(round))
         .sum::<i32>()
 }
+
+#[test]
+fn test_rules() {
+    let standard = Rules::standard().expect("🪨");
+    assert_eq!(
+        standard.score_p1((standard.theirs(b'A'), standard.mine(b'Y'))),
+        8
+    );
+    assert_eq!(
+        standard.score_p2((standard.theirs(b'A'), standard.to_ldw(b'Y'))),
+        4
+    );
+
+    let rpsls = Rules::parse(
+        read_lines("input/day_2_rules_rpsls.txt")
+            .expect("🦎")
+            .map(|l| l.expect("🖖")),
+    )
+    .expect("🦎");
+
+    // Spock vaporizes rock, and paper is listed before lizard as a way to beat
+    // Spock
+    assert_eq!(rpsls.score_p1((rpsls.theirs(b'A'), rpsls.mine(b'Z'))), 11);
+    assert_eq!(rpsls.score_p2((rpsls.theirs(b'E'), rpsls.to_ldw(b'Z'))), 8);
+
+    let broken = Rules::parse(
+        STANDARD_RULES
+            .lines()
+            .filter(|line| !line.starts_with("move paper"))
+            .map(str::to_owned),
+    )
+    .expect_err("paper isn't a move");
+    assert_eq!(
+        (broken.line, broken.reason.as_str()),
+        (4, "Unknown move paper")
+    );
+
+    let no_win = Rules::parse(
+        STANDARD_RULES
+            .lines()
+            .filter(|line| !line.starts_with("outcome win"))
+            .map(str::to_owned),
+    )
+    .expect_err("Can't win");
+    assert_eq!((no_win.line, no_win.text.as_str()), (8, "outcome draw 3 Y"));
+}
+
+#[test]
+fn test_plan() {
+    let rules = Rules::standard().expect("🪨");
+    let plan = rules.plan(["A Y", "B X", "C Z"].map(str::to_owned).into_iter());
+
+    assert_eq!(plan.guide_score, 15);
+    assert_eq!(plan.best_mapping_score, 24);
+    assert_eq!(plan.best_response_score, 24);
+    assert_eq!(plan.shortfall(), 9);
+    assert_eq!(plan.best_mapping, vec![RPS(2), RPS(1), RPS(0)]);
+
+    // Every one of their moves does no better than the value against the mix
+    assert!((plan.mixed.iter().sum::<f64>() - 1.).abs() < 1e-9);
+    for t in 0..3 {
+        let expected = (0..3)
+            .map(|m| plan.mixed[m] * rules.payoff[t][m] as f64)
+            .sum::<f64>();
+        assert!(expected >= plan.mixed_value - 1e-9);
+    }
+    assert!(plan.mixed_value > 3.);
+}
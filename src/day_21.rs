@@ -9,6 +9,10 @@ use nom::{
     IResult,
 };
 
+use crate::checked::{
+    add, div, mul, sub, Answer, ArithmeticError, SolverError,
+};
+
 #[derive(Debug, Clone, Copy)]
 enum Op {
     Add,
@@ -74,40 +78,48 @@ fn parse_monkey(line: String) -> (String, MonkeyNode) {
 }
 
 impl Op {
-    fn apply(self, lhs: i64, rhs: i64) -> i64 {
+    fn apply(self, lhs: i64, rhs: i64) -> Result<i64, ArithmeticError> {
         use Op::*;
 
         match self {
-            Add => lhs + rhs,
-            Sub => lhs - rhs,
-            Mul => lhs * rhs,
-            Div => lhs / rhs,
+            Add => add(lhs, rhs),
+            Sub => sub(lhs, rhs),
+            Mul => mul(lhs, rhs),
+            Div => div(lhs, rhs),
         }
     }
 
     // Take the inverse of this operation to find the rhs of the expression
     // ans = lhs {op} ?
-    fn solve_for_right(self, ans: i64, lhs: i64) -> i64 {
+    fn solve_for_right(
+        self,
+        ans: i64,
+        lhs: i64,
+    ) -> Result<i64, ArithmeticError> {
         use Op::*;
 
         match self {
-            Add => ans - lhs,
-            Sub => lhs - ans,
-            Mul => ans / lhs,
-            Div => lhs / ans,
+            Add => sub(ans, lhs),
+            Sub => sub(lhs, ans),
+            Mul => div(ans, lhs),
+            Div => div(lhs, ans),
         }
     }
 
     // Take the inverse of this operation to find the lhs of the expression
     // ans = ? {op} rhs
-    fn solve_for_left(self, ans: i64, rhs: i64) -> i64 {
+    fn solve_for_left(
+        self,
+        ans: i64,
+        rhs: i64,
+    ) -> Result<i64, ArithmeticError> {
         use Op::*;
 
         match self {
-            Add => ans - rhs,
-            Sub => rhs + ans,
-            Mul => ans / rhs,
-            Div => rhs * ans,
+            Add => sub(ans, rhs),
+            Sub => add(rhs, ans),
+            Mul => div(ans, rhs),
+            Div => mul(rhs, ans),
         }
     }
 }
@@ -123,10 +135,13 @@ impl Operand {
 }
 
 impl MonkeyNode {
-    fn eval(&self, knowns: &HashMap<String, i64>) -> Option<i64> {
+    fn eval(
+        &self,
+        knowns: &HashMap<String, i64>,
+    ) -> Option<Result<i64, ArithmeticError>> {
         use MonkeyNode::*;
         match self {
-            Literal(val) => Some(*val),
+            Literal(val) => Some(Ok(*val)),
             Statement(lhs, op, rhs) => {
                 let left = lhs.eval(knowns)?;
                 let right = rhs.eval(knowns)?;
@@ -137,6 +152,28 @@ impl MonkeyNode {
     }
 }
 
+/// Evaluate every monkey whose operands are already known, in order, and hand
+/// back the ones that are still waiting on something
+fn resolve(
+    monkeys: Vec<(String, MonkeyNode)>,
+    knowns: &mut HashMap<String, i64>,
+) -> Result<Vec<(String, MonkeyNode)>, SolverError> {
+    let mut unresolved = vec![];
+
+    for (name, node) in monkeys {
+        match node.eval(knowns) {
+            Some(val) => {
+                let val =
+                    val.map_err(|e| e.context(format!("monkey {name}")))?;
+                knowns.insert(name, val);
+            }
+            None => unresolved.push((name, node)),
+        }
+    }
+
+    Ok(unresolved)
+}
+
 #[derive(Debug, Clone, Copy)]
 enum SolverNode {
     LeftKnown(i64, Op, usize),
@@ -182,80 +219,76 @@ impl SolverNode {
     }
 }
 
-fn solve_for_humn(root: usize, nodes: Vec<SolverNode>) -> i64 {
+fn solve_for_humn(
+    root: usize,
+    nodes: Vec<SolverNode>,
+    names: &[String],
+) -> Result<i64, SolverError> {
     use SolverNode::*;
 
     let mut curr = root;
     let mut result = 0;
 
     loop {
+        let undoing = |e: ArithmeticError| {
+            e.context(format!("solving monkey {} for humn", names[curr]))
+        };
+
         match nodes.get(curr).expect("🦀") {
             Root(next, val) => {
                 curr = *next;
                 result = *val;
             }
             LeftKnown(val, op, next) => {
+                result = op.solve_for_right(result, *val).map_err(undoing)?;
                 curr = *next;
-                result = op.solve_for_right(result, *val);
             }
             RightKnown(next, op, val) => {
+                result = op.solve_for_left(result, *val).map_err(undoing)?;
                 curr = *next;
-                result = op.solve_for_left(result, *val);
             }
             Humn => break,
         }
     }
 
-    result
+    Ok(result)
 }
 
 /**** Problem 1 ******/
 
-pub fn problem_1<I>(input_lines: I) -> impl Display
+fn find_root<I>(input_lines: I) -> Result<i64, SolverError>
 where
     I: Iterator<Item = String>,
 {
     let mut knowns = HashMap::new();
-    let mut unknowns = input_lines
-        .map(parse_monkey)
-        .filter_map(|(name, node)| {
-            if let Some(val) = node.eval(&knowns) {
-                knowns.insert(name, val);
-                None
-            } else {
-                Some((name, node))
-            }
-        })
-        .collect::<Vec<_>>();
+    let monkeys = input_lines.map(parse_monkey).collect();
+    let mut unknowns = resolve(monkeys, &mut knowns)?;
 
-    let mut new_unknowns = vec![];
     while !knowns.contains_key("root") {
-        new_unknowns.extend(unknowns.drain(..).filter_map(|(name, node)| {
-            if let Some(val) = node.eval(&knowns) {
-                knowns.insert(name, val);
-                None
-            } else {
-                Some((name, node))
-            }
-        }));
-
-        std::mem::swap(&mut new_unknowns, &mut unknowns);
+        unknowns = resolve(unknowns, &mut knowns)?;
     }
 
-    *knowns.get("root").expect("🐒")
+    Ok(*knowns.get("root").expect("🐒"))
+}
+
+pub fn problem_1<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    Answer(find_root(input_lines))
 }
 
 /**** Problem 2 ******/
 
-pub fn problem_2<I>(input_lines: I) -> impl Display
+fn find_humn<I>(input_lines: I) -> Result<i64, SolverError>
 where
     I: Iterator<Item = String>,
 {
     let mut knowns = HashMap::new();
 
-    let mut unknowns = input_lines
+    let monkeys = input_lines
         .map(parse_monkey)
-        .filter_map(|(name, mut node)| {
+        .map(|(name, mut node)| {
             if &name == "root" {
                 if let MonkeyNode::Statement(lhs, _, rhs) = node {
                     node = MonkeyNode::Req(lhs, rhs)
@@ -267,32 +300,19 @@ where
                 node = MonkeyNode::Human;
             }
 
-            if let Some(val) = node.eval(&knowns) {
-                knowns.insert(name, val);
-                None
-            } else {
-                Some((name, node))
-            }
+            (name, node)
         })
-        .collect::<Vec<_>>();
-
-    let mut new_unknowns = vec![];
+        .collect();
 
-    let mut resolutions = 1;
+    let mut unknowns = resolve(monkeys, &mut knowns)?;
 
-    while resolutions > 0 {
-        resolutions = 0;
-        new_unknowns.extend(unknowns.drain(..).filter_map(|(name, node)| {
-            if let Some(val) = node.eval(&knowns) {
-                knowns.insert(name, val);
-                resolutions += 1;
-                None
-            } else {
-                Some((name, node))
-            }
-        }));
+    loop {
+        let unresolved = unknowns.len();
+        unknowns = resolve(unknowns, &mut knowns)?;
 
-        std::mem::swap(&mut new_unknowns, &mut unknowns);
+        if unknowns.len() == unresolved {
+            break;
+        }
     }
 
     // At this point all nodes that can be evaluated have been evaluated.
@@ -309,11 +329,18 @@ where
         .map(|(i, (n, _))| (n.clone(), i))
         .collect::<HashMap<_, _>>();
 
-    let to_solve = unknowns
+    let (names, to_solve): (Vec<_>, Vec<_>) = unknowns
         .into_iter()
-        .map(|(_, node)| SolverNode::new(node, &knowns, &name_to_id))
-        .collect::<Vec<_>>();
+        .map(|(name, node)| (name, SolverNode::new(node, &knowns, &name_to_id)))
+        .unzip();
 
     let root = *name_to_id.get("root").expect("🦧");
-    solve_for_humn(root, to_solve)
+    solve_for_humn(root, to_solve, &names)
+}
+
+pub fn problem_2<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    Answer(find_humn(input_lines))
 }
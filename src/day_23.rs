@@ -1,105 +1,71 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
+    iter::successors,
 };
 
 use iter_tools::Itertools;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Dir {
-    N,
-    E,
-    S,
-    W,
-}
-
-impl Dir {
-    fn next(&self) -> Self {
-        use Dir::*;
-        match self {
-            N => S,
-            S => W,
-            W => E,
-            E => N,
-        }
-    }
-
-    fn can_move(&self, directions: &[bool; 8]) -> bool {
-        use Dir::*;
-
-        match self {
-            N => !(directions[7] || directions[0] || directions[1]),
-            E => !(directions[1] || directions[2] || directions[3]),
-            S => !(directions[3] || directions[4] || directions[5]),
-            W => !(directions[5] || directions[6] || directions[7]),
-        }
+use crate::geometry::{Dir4, Dir8, Point2};
+use crate::parsing::CharGrid;
+
+/// The order elves consider directions in, starting from any of them
+fn next_proposal(d: Dir4) -> Dir4 {
+    use Dir4::*;
+    match d {
+        N => S,
+        S => W,
+        W => E,
+        E => N,
     }
+}
 
-    fn apply(&self, (x, y): (i32, i32)) -> (i32, i32) {
-        use Dir::*;
-        match self {
-            N => (x, y - 1),
-            S => (x, y + 1),
-            W => (x - 1, y),
-            E => (x + 1, y),
-        }
+/// Find where the elf at the given spot wants to move, trying directions in
+/// proposal order starting from `first`
+fn find_next_move(
+    first: Dir4,
+    p: Point2,
+    occupied: &HashSet<Point2>,
+) -> Option<Point2> {
+    // Indexed by Dir8 discriminant: N, NE, E, SE, S, SW, W, NW
+    let blocked = Dir8::ALL.map(|d| occupied.contains(&d.apply(p)));
+
+    if blocked.iter().all(|b| !*b) {
+        return None;
     }
 
-    fn find_next_move(
-        &self,
-        (x, y): (i32, i32),
-        occupied: &HashSet<(i32, i32)>,
-    ) -> Option<(i32, i32)> {
-        // N, NE, E, SE, S, SW, W, NW
-        let directions_blocked = [
-            occupied.contains(&(x, y - 1)),
-            occupied.contains(&(x + 1, y - 1)),
-            occupied.contains(&(x + 1, y)),
-            occupied.contains(&(x + 1, y + 1)),
-            occupied.contains(&(x, y + 1)),
-            occupied.contains(&(x - 1, y + 1)),
-            occupied.contains(&(x - 1, y)),
-            occupied.contains(&(x - 1, y - 1)),
-        ];
-
-        if directions_blocked.iter().all(|i| !*i) {
-            return None;
-        }
-
-        let mut curr = *self;
-
-        for _ in 0..4 {
-            if curr.can_move(&directions_blocked) {
-                return Some(curr.apply((x, y)));
-            }
-            curr = curr.next();
-        }
-
-        None
-    }
+    let can_move = |d: Dir4| {
+        let d = Dir8::from(d);
+        [d.left(), d, d.right()]
+            .into_iter()
+            .all(|d| !blocked[d as usize])
+    };
+
+    successors(Some(first), |d| Some(next_proposal(*d)))
+        .take(4)
+        .find(|d| can_move(*d))
+        .map(|d| d.apply(p))
 }
 
-fn parse_elf_locations(
-    y: usize,
-    line: String,
-) -> impl Iterator<Item = (i32, i32)> {
-    line.into_bytes()
-        .into_iter()
-        .enumerate()
-        .filter_map(|(x, v)| (v == b'#').then_some(x))
-        .map(move |x| (x as i32, y as i32))
+fn parse_elf_locations<I>(input_lines: I) -> Vec<Point2>
+where
+    I: Iterator<Item = String>,
+{
+    CharGrid::from_lines(input_lines, '.')
+        .iter()
+        .filter_map(|(p, c)| (c == '#').then_some(p))
+        .collect_vec()
 }
 
 fn run_simulation<I>(input_lines: I, max_rounds: Option<usize>) -> (i32, usize)
 where
     I: Iterator<Item = String>,
 {
-    use Dir::*;
+    use Dir4::*;
 
     let mut locations = HashSet::new();
-    let mut elves = input_lines
-        .enumerate()
-        .flat_map(|(y, line)| parse_elf_locations(y, line))
+    let mut elves = parse_elf_locations(input_lines)
+        .into_iter()
         .map(|e| {
             locations.insert(e);
             (e, N, None)
@@ -119,7 +85,7 @@ where
 
         for i in 0..elves.len() {
             let (p, d, _) = elves[i];
-            let plan_opt = d.find_next_move(p, &locations);
+            let plan_opt = find_next_move(d, p, &locations);
 
             if let Some(plan) = plan_opt {
                 if let Some(prev_e) = planned_moves.insert(plan, i) {
@@ -130,7 +96,7 @@ where
                 }
             }
 
-            elves[i].1 = d.next();
+            elves[i].1 = next_proposal(d);
         }
 
         // Apply Moves
@@ -147,17 +113,16 @@ where
         rounds += 1;
     }
 
-    let mut min = (i32::MAX, i32::MAX);
-    let mut max = (i32::MIN, i32::MIN);
+    let mut min = Point2::new(i32::MAX, i32::MAX);
+    let mut max = Point2::new(i32::MIN, i32::MIN);
 
-    elves.iter().map(|(p, _, _)| p).for_each(|(x, y)| {
-        min.0 = min.0.min(*x);
-        min.1 = min.1.min(*y);
-        max.0 = max.0.max(*x);
-        max.1 = max.1.max(*y);
+    elves.iter().map(|(p, _, _)| p).for_each(|p| {
+        min = min.min(*p);
+        max = max.max(*p);
     });
 
-    let size = (max.0 - min.0 + 1) * (max.1 - min.1 + 1) - elves.len() as i32;
+    let Point2 { x: w, y: h } = max - min + Point2::new(1, 1);
+    let size = w * h - elves.len() as i32;
 
     (size, rounds)
 }
@@ -1,74 +1,22 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
+    iter::once,
 };
 
 use iter_tools::Itertools;
-use nom::{
-    branch::alt, character::complete::char, combinator::map, multi::many1,
-    sequence::tuple, IResult,
-};
-use priority_queue::PriorityQueue;
-
-enum Dir {
-    N,
-    S,
-    E,
-    W,
-}
 
-impl Dir {
-    fn from_c(c: char) -> Option<Self> {
-        match c {
-            '^' => Some(Dir::N),
-            'v' => Some(Dir::S),
-            '<' => Some(Dir::W),
-            '>' => Some(Dir::E),
-            _ => None,
-        }
-    }
-}
-
-enum Line {
-    Start(i32),
-    End(i32),
-    Blizzards(Vec<(i32, Dir)>),
-}
-
-fn parse_line(line: String) -> Line {
-    let result: IResult<&str, Line> = map(
-        tuple((
-            many1(char('#')),
-            many1(alt((char('.'), char('^'), char('>'), char('v'), char('<')))),
-            many1(char('#')),
-        )),
-        |(pre, content, post)| {
-            if post.len() > 1 {
-                Line::Start(pre.len() as i32)
-            } else if pre.len() > 1 {
-                Line::End(pre.len() as i32)
-            } else {
-                Line::Blizzards(
-                    content
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(i, c)| {
-                            Dir::from_c(c).map(|d| (i as i32, d))
-                        })
-                        .collect_vec(),
-                )
-            }
-        },
-    )(&line);
-
-    result.expect("🦖").1
-}
+use crate::geometry::{Dir4, Point2};
+use crate::parsing::CharGrid;
+use crate::search::astar;
+use crate::topology::Torus;
 
 struct Map {
     width: i32,
     height: i32,
-    start: (i32, i32),
-    finish: (i32, i32),
+    valley: Torus,
+    start: Point2,
+    finish: Point2,
     h_storms: HashMap<i32, Vec<(i32, i32)>>,
     v_storms: HashMap<i32, Vec<(i32, i32)>>,
 }
@@ -77,38 +25,32 @@ fn read_map<I>(input_lines: I) -> Map
 where
     I: Iterator<Item = String>,
 {
-    use Dir::*;
+    use Dir4::*;
+
+    let grid = CharGrid::from_lines(input_lines, '#');
+
+    // Everything inside the walls is shifted up and left by one so the valley
+    // starts at 0,0
+    let width = grid.width as i32 - 2;
+    let height = grid.height as i32 - 2;
 
-    let mut start: (i32, i32) = (0, -1);
-    let mut finish: (i32, i32) = (0, 0);
-    let mut width = 0;
-    let mut height = 0;
+    let opening = |y: usize| {
+        grid.row(y).iter().position(|c| *c == '.').expect("🦖") as i32 - 1
+    };
+
+    let start = Point2::new(opening(0), -1);
+    let finish = Point2::new(opening(grid.height - 1), height);
 
     let mut b_by_x: HashMap<i32, Vec<(i32, i32)>> = HashMap::new();
     let mut b_by_y: HashMap<i32, Vec<(i32, i32)>> = HashMap::new();
 
-    input_lines
-        .map(parse_line)
-        .enumerate()
-        .for_each(|(y, line)| match line {
-            Line::Start(s) => {
-                start.0 = s - 1;
-            }
-            Line::Blizzards(bs) => bs.into_iter().for_each(|(x, d)| {
-                let x = x as i32;
-                let y = y as i32 - 1;
-                match d {
-                    N => b_by_x.entry(x).or_default().push((y, -1)),
-                    S => b_by_x.entry(x).or_default().push((y, 1)),
-                    E => b_by_y.entry(y).or_default().push((x, 1)),
-                    W => b_by_y.entry(y).or_default().push((x, -1)),
-                }
-            }),
-            Line::End(e) => {
-                finish = (e - 1, y as i32 - 1);
-                width = e;
-                height = y as i32 - 1;
-            }
+    grid.iter()
+        .filter_map(|(p, c)| {
+            Dir4::from_arrow(c).map(|d| (p - Point2::new(1, 1), d))
+        })
+        .for_each(|(p, d)| match d {
+            N | S => b_by_x.entry(p.x).or_default().push((p.y, d.delta().y)),
+            E | W => b_by_y.entry(p.y).or_default().push((p.x, d.delta().x)),
         });
 
     Map {
@@ -116,73 +58,69 @@ where
         finish,
         height,
         width,
+        valley: Torus::new(width, height),
         h_storms: b_by_y,
         v_storms: b_by_x,
     }
 }
 
-fn get_possible_moves(
-    (x, y): (i32, i32),
-    t: i32,
-    map: &Map,
-) -> Vec<(i32, i32)> {
+fn get_possible_moves(p: Point2, t: i32, map: &Map) -> Vec<Point2> {
     let mut blocked = HashSet::new();
 
-    [x - 1, x, x + 1]
+    // Blizzards wrap around the valley, so their position at time `t` is just
+    // their straight-line position wrapped back onto the torus
+    [p.x - 1, p.x, p.x + 1]
         .into_iter()
-        .map(|c| c.rem_euclid(map.width))
+        .map(|c| map.valley.wrap(Point2::new(c, 0)).x)
         .filter_map(|c| map.v_storms.get(&c).map(|v| (c, v)))
         .for_each(|(c, v)| {
             v.iter().for_each(|(start, delta)| {
-                blocked.insert((c, (start + t * delta).rem_euclid(map.height)));
+                blocked
+                    .insert(map.valley.wrap(Point2::new(c, start + t * delta)));
             })
         });
 
-    [y - 1, y, y + 1]
+    [p.y - 1, p.y, p.y + 1]
         .into_iter()
-        .map(|c| c.rem_euclid(map.height))
+        .map(|c| map.valley.wrap(Point2::new(0, c)).y)
         .filter_map(|c| map.h_storms.get(&c).map(|v| (c, v)))
         .for_each(|(c, v)| {
             v.iter().for_each(|(start, delta)| {
-                blocked.insert(((start + t * delta).rem_euclid(map.width), c));
+                blocked
+                    .insert(map.valley.wrap(Point2::new(start + t * delta, c)));
             })
         });
 
-    [(x, y), (x, y + 1), (x + 1, y), (x, y - 1), (x - 1, y)]
-        .into_iter()
-        .filter(|(x, y)| {
-            ((*x, *y) == map.start)
-                || ((*x, *y) == map.finish)
-                || (*x >= 0 && *y >= 0 && *x < map.width && *y < map.height)
+    once(p)
+        .chain(p.neighbors4())
+        .filter(|n| {
+            *n == map.start
+                || *n == map.finish
+                || (n.x >= 0 && n.y >= 0 && n.x < map.width && n.y < map.height)
         })
-        .filter(|d| !blocked.contains(d))
+        .filter(|n| !blocked.contains(n))
         .collect_vec()
 }
 
 /// Run an A* search on the map from the start to the finish using the storm
 /// rules on each step to determine possible moves
 fn run_search(map: &Map, start_t: i32) -> i32 {
-    let mut search = PriorityQueue::new();
-
-    let priority = |p: (i32, i32), d: i32| {
-        -((map.finish.0 - p.0).abs() + (map.finish.1 - p.1).abs() + d)
+    let moves = |(p, t): &(Point2, i32)| {
+        get_possible_moves(*p, t + 1, map)
+            .into_iter()
+            .map(|n| ((n, t + 1), 1))
+            .collect_vec()
     };
 
-    search.push((map.start, start_t), priority(map.start, start_t));
-
-    loop {
-        let ((p, mut t), _) = search.pop().expect("🐝");
-
-        if p == map.finish {
-            return t;
-        }
-
-        t += 1;
-
-        get_possible_moves(p, t, map).into_iter().for_each(|n| {
-            search.push((n, t), priority(n, t));
-        });
-    }
+    astar(
+        (map.start, start_t),
+        &moves,
+        |(p, _)| *p == map.finish,
+        |(p, _)| p.manhattan(map.finish),
+    )
+    .goal
+    .map(|(_, t)| t)
+    .expect("🐝")
 }
 
 /**** Problem 1 ******/
@@ -1,11 +1,11 @@
 use iter_tools::Itertools;
-use lazy_static::lazy_static;
-use std::{array::from_fn, fmt::Display};
+use std::fmt::Display;
 
-lazy_static! {
-    /// Each letter gets a bit in a u64 based on its point value
-    static ref FLAGS: [u64; 26 * 2 + 1] = from_fn(|i| 1_u64 << i);
-}
+use crate::bitset::BitSet64;
+use crate::helpers::is_report;
+
+/// Number of elves in a group for problem 2
+const GROUP_SIZE: usize = 3;
 
 /// Convert an item letter into the points it represents
 fn to_points(item: u8) -> u8 {
@@ -16,53 +16,286 @@ fn to_points(item: u8) -> u8 {
     }
 }
 
+/// Convert points back into the item letter
+fn to_item(points: usize) -> char {
+    match points {
+        1..=26 => (b'a' + points as u8 - 1) as char,
+        _ => (b'A' + points as u8 - 27) as char,
+    }
+}
+
 /// Extract the information about which items are present in the form of a
-/// u64 where each item is signified by a single bit
-fn to_flags(items: &[u8]) -> u64 {
-    items.iter().fold(0_u64, |flags, item| {
-        flags | FLAGS[to_points(*item) as usize]
-    })
+/// bitset where each item is signified by the bit for its point value
+fn to_flags(items: &[u8]) -> BitSet64 {
+    items.iter().map(|item| to_points(*item) as usize).collect()
+}
+
+/// Every item in the set, lowest points first
+fn to_items(flags: BitSet64) -> Vec<char> {
+    flags.iter().map(to_item).collect()
+}
+
+/// Something wrong with a rucksack or a group of them. Rucksacks are numbered
+/// by line and groups by position, both starting at 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Diagnostic {
+    InvalidItem { line: usize, item: char },
+    OddLength { line: usize, len: usize },
+    NoDuplicate { line: usize },
+    MultipleDuplicates { line: usize, items: Vec<char> },
+    IncompleteGroup { group: usize, size: usize },
+    NoBadge { group: usize },
+    MultipleBadges { group: usize, items: Vec<char> },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Diagnostic::*;
+
+        match self {
+            InvalidItem { line, item } => {
+                write!(f, "line {line}: {item:?} isn't an item")
+            }
+            OddLength { line, len } => write!(
+                f,
+                "line {line}: {len} items can't be split into compartments"
+            ),
+            NoDuplicate { line } => {
+                write!(f, "line {line}: no item is in both compartments")
+            }
+            MultipleDuplicates { line, items } => write!(
+                f,
+                "line {line}: {} are all in both compartments",
+                items.iter().join(", ")
+            ),
+            IncompleteGroup { group, size } => {
+                write!(f, "group {group}: only {size} elves")
+            }
+            NoBadge { group } => {
+                write!(f, "group {group}: no item is carried by everyone")
+            }
+            MultipleBadges { group, items } => write!(
+                f,
+                "group {group}: {} could all be the badge",
+                items.iter().join(", ")
+            ),
+        }
+    }
+}
+
+/// Items in the rucksack on the given line, provided they're all letters
+fn checked_flags(line: usize, items: &[u8]) -> Result<BitSet64, Diagnostic> {
+    match items.iter().find(|item| !item.is_ascii_alphabetic()) {
+        Some(item) => Err(Diagnostic::InvalidItem {
+            line,
+            item: *item as char,
+        }),
+        None => Ok(to_flags(items)),
+    }
+}
+
+/// Points for the one item in the set, or the right complaint if there isn't
+/// exactly one
+fn only_item<F>(flags: BitSet64, too_many: F) -> Result<u32, Option<Diagnostic>>
+where
+    F: FnOnce(Vec<char>) -> Diagnostic,
+{
+    match flags.len() {
+        0 => Err(None),
+        1 => Ok(flags.first().expect("🎒") as u32),
+        _ => Err(Some(too_many(to_items(flags)))),
+    }
 }
 
-fn check_for_p1_errors(items: &[u8]) -> u32 {
+/// Items in both compartments of the rucksack on the given line
+fn shared_in_rucksack(
+    line: usize,
+    items: &[u8],
+) -> Result<BitSet64, Diagnostic> {
+    if !items.len().is_multiple_of(2) {
+        return Err(Diagnostic::OddLength {
+            line,
+            len: items.len(),
+        });
+    }
+
     let (left, right) = items.split_at(items.len() / 2);
 
-    let left_flags = to_flags(left);
-    let right_flags = to_flags(right);
+    let left_flags = checked_flags(line, left)?;
+    let right_flags = checked_flags(line, right)?;
+
+    Ok(left_flags.intersection(&right_flags))
+}
+
+fn check_for_p1_errors(line: usize, items: &[u8]) -> Result<u32, Diagnostic> {
+    let shared = shared_in_rucksack(line, items)?;
+
+    only_item(shared, |items| Diagnostic::MultipleDuplicates {
+        line,
+        items,
+    })
+    .map_err(|e| e.unwrap_or(Diagnostic::NoDuplicate { line }))
+}
+
+/// Add up the points, or every reason some rucksacks or groups don't have
+/// any
+fn total_points<I>(points: I) -> Result<u32, Vec<Diagnostic>>
+where
+    I: Iterator<Item = Result<u32, Diagnostic>>,
+{
+    let (points, problems): (Vec<_>, Vec<_>) = points.partition_result();
+
+    match problems.is_empty() {
+        true => Ok(points.into_iter().sum()),
+        false => Err(problems),
+    }
+}
+
+/// Total points, complaining about every problem before giving up
+fn answer<I>(points: I) -> u32
+where
+    I: Iterator<Item = Result<u32, Diagnostic>>,
+{
+    total_points(points).unwrap_or_else(|problems| {
+        for problem in &problems {
+            eprintln!("⚠️ {problem}");
+        }
 
-    (left_flags & right_flags).trailing_zeros()
+        panic!("🎒 {} problems with the rucksacks", problems.len())
+    })
 }
 
 pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    input_lines
-        .map(|line| check_for_p1_errors(line.as_bytes()))
-        .sum::<u32>()
+    answer(
+        input_lines
+            .enumerate()
+            .map(|(i, line)| check_for_p1_errors(i + 1, line.as_bytes())),
+    )
 }
 
 /**** Problem 2 ******/
 
-fn find_badge(elf_1: &[u8], elf_2: &[u8], elf_3: &[u8]) -> u32 {
-    let flags_1 = to_flags(elf_1);
-    let flags_2 = to_flags(elf_2);
-    let flags_3 = to_flags(elf_3);
+/// Items carried by every elf in the given group, which should have `size`
+/// elves in it
+fn shared_in_group(
+    group: usize,
+    size: usize,
+    elves: &[&[u8]],
+) -> Result<BitSet64, Diagnostic> {
+    if elves.len() != size {
+        return Err(Diagnostic::IncompleteGroup {
+            group,
+            size: elves.len(),
+        });
+    }
+
+    let first_line = (group - 1) * size + 1;
+
+    elves
+        .iter()
+        .enumerate()
+        .map(|(i, elf)| checked_flags(first_line + i, elf))
+        .reduce(|shared, flags| Ok(shared?.intersection(&flags?)))
+        .unwrap_or(Ok(BitSet64::new()))
+}
+
+/// Points for the one item carried by every elf in a group of `size`
+fn find_badge(
+    group: usize,
+    size: usize,
+    elves: &[&[u8]],
+) -> Result<u32, Diagnostic> {
+    let shared = shared_in_group(group, size, elves)?;
 
-    (flags_1 & flags_2 & flags_3).trailing_zeros()
+    only_item(shared, |items| Diagnostic::MultipleBadges { group, items })
+        .map_err(|e| e.unwrap_or(Diagnostic::NoBadge { group }))
+}
+
+/// Split rucksacks up into groups of the given size, numbered from 1
+fn groups(
+    rucksacks: &[String],
+    size: usize,
+) -> impl Iterator<Item = (usize, Vec<&[u8]>)> {
+    rucksacks.chunks(size).enumerate().map(|(i, group)| {
+        (i + 1, group.iter().map(|elf| elf.as_bytes()).collect_vec())
+    })
 }
 
 pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    input_lines
-        .tuples::<(String, String, String)>()
-        .into_iter()
-        .map(|(elf_1, elf_2, elf_3)| {
-            find_badge(elf_1.as_bytes(), elf_2.as_bytes(), elf_3.as_bytes())
-        })
-        .sum::<u32>()
+    let rucksacks = input_lines.collect_vec();
+
+    if is_report() {
+        println!();
+        print!("{}", Report::new(rucksacks.iter().cloned(), GROUP_SIZE));
+    }
+
+    answer(
+        groups(&rucksacks, GROUP_SIZE)
+            .map(|(group, elves)| find_badge(group, GROUP_SIZE, &elves)),
+    )
+}
+
+/**** Report ******/
+
+/// Every item shared within each rucksack and within each group of the
+/// given size, rather than just the one the puzzle cares about
+#[derive(Debug)]
+struct Report {
+    rucksacks: Vec<Result<Vec<char>, Diagnostic>>,
+    groups: Vec<Result<Vec<char>, Diagnostic>>,
+}
+
+impl Report {
+    fn new<I>(input_lines: I, group_size: usize) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let rucksacks = input_lines.collect_vec();
+
+        Self {
+            rucksacks: rucksacks
+                .iter()
+                .enumerate()
+                .map(|(i, items)| shared_in_rucksack(i + 1, items.as_bytes()))
+                .map(|shared| shared.map(to_items))
+                .collect(),
+            groups: groups(&rucksacks, group_size)
+                .map(|(group, elves)| {
+                    shared_in_group(group, group_size, &elves).map(to_items)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut show =
+            |kind: &str, shared: &[Result<Vec<char>, Diagnostic>]| {
+                shared.iter().enumerate().try_for_each(|(i, shared)| {
+                    match shared {
+                        Ok(items) => {
+                            writeln!(
+                                f,
+                                "{kind} {}: {}",
+                                i + 1,
+                                items.iter().join("")
+                            )
+                        }
+                        Err(e) => writeln!(f, "{kind} {}: ⚠️ {e}", i + 1),
+                    }
+                })
+            };
+
+        show("rucksack", &self.rucksacks)?;
+        show("group", &self.groups)
+    }
 }
 
 #[test]
@@ -75,11 +308,98 @@ fn test_to_points() {
 
 #[test]
 fn test_to_flags() {
-    assert_eq!(0b10_u64, to_flags(b"a"));
-    assert_eq!(0b111000000000000000000000001110_u64, to_flags(b"abcABC"));
+    assert_eq!(BitSet64::from_words([0b10_u64]), to_flags(b"a"));
+    assert_eq!(
+        BitSet64::from_words([0b111000000000000000000000001110_u64]),
+        to_flags(b"abcABC")
+    );
 }
 
 #[test]
 fn test_check_for_p1_errors() {
-    assert_eq!(check_for_p1_errors(b"aa"), 1);
+    use Diagnostic::*;
+
+    assert_eq!(check_for_p1_errors(1, b"aa"), Ok(1));
+    assert_eq!(
+        check_for_p1_errors(2, b"abc"),
+        Err(OddLength { line: 2, len: 3 })
+    );
+    assert_eq!(check_for_p1_errors(3, b"ab"), Err(NoDuplicate { line: 3 }));
+    assert_eq!(
+        check_for_p1_errors(4, b"aZZa"),
+        Err(MultipleDuplicates {
+            line: 4,
+            items: vec!['a', 'Z']
+        })
+    );
+    assert_eq!(
+        check_for_p1_errors(5, b"a1a1"),
+        Err(InvalidItem { line: 5, item: '1' })
+    );
+}
+
+#[test]
+fn test_report() {
+    let lines = ["abcb", "bdbx", "zbzA", "bAbA", "Aq"].map(str::to_owned);
+    let report = Report::new(lines.into_iter(), 2);
+
+    assert_eq!(report.rucksacks[0], Ok(vec!['b']));
+    assert_eq!(report.rucksacks[3], Ok(vec!['b', 'A']));
+    assert_eq!(report.rucksacks[4], Ok(vec![]));
+    assert_eq!(report.groups[..2], [Ok(vec!['b']), Ok(vec!['b', 'A'])]);
+    assert_eq!(
+        report.groups[2],
+        Err(Diagnostic::IncompleteGroup { group: 3, size: 1 })
+    );
+    assert_eq!(
+        find_badge(2, 3, &[b"zbzA", b"bAbA", b"Ab"]),
+        Err(Diagnostic::MultipleBadges {
+            group: 2,
+            items: vec!['b', 'A']
+        })
+    );
+}
+
+#[test]
+fn test_find_badge_any_size() {
+    let rucksacks = ["abcb", "bdbx", "zbzA", "bAqA"].map(str::to_owned);
+
+    let pairs = groups(&rucksacks, 2)
+        .map(|(group, elves)| find_badge(group, 2, &elves))
+        .collect_vec();
+    assert_eq!(
+        pairs,
+        vec![
+            Ok(2),
+            Err(Diagnostic::MultipleBadges {
+                group: 2,
+                items: vec!['b', 'A']
+            })
+        ]
+    );
+
+    let (group, elves) = groups(&rucksacks, 4).next().expect("🎒");
+    assert_eq!(find_badge(group, 4, &elves), Ok(2));
+    assert_eq!(
+        find_badge(group, 4, &elves[..3]),
+        Err(Diagnostic::IncompleteGroup { group: 1, size: 3 })
+    );
+}
+
+#[test]
+fn test_total_points() {
+    use Diagnostic::*;
+
+    assert_eq!(total_points([Ok(1), Ok(27)].into_iter()), Ok(28));
+    assert_eq!(
+        total_points(
+            [
+                Err(NoDuplicate { line: 1 }),
+                Ok(2),
+                Err(NoBadge { group: 1 })
+            ]
+            .into_iter()
+        ),
+        Err(vec![NoDuplicate { line: 1 }, NoBadge { group: 1 }])
+    );
 }
@@ -2,28 +2,49 @@ use std::fmt::Display;
 
 use iter_tools::Itertools;
 
-/// parse a line from the schedule into a tuple of the 4 numbers represented
-/// by the schedule: (start_1, end_1, start_2, end_2)
-fn parse_schedule_line(line: String) -> (u32, u32, u32, u32) {
-    line.splitn(4, &['-', ','])
-        .map(|str_val| str_val.parse::<u32>().expect("Valid input guaranteed"))
+use crate::helpers::is_report;
+use crate::interval::{Interval, IntervalIndex, Relation};
+
+/// parse a line from the schedule into the pair of section ranges assigned to
+/// the two elves
+fn parse_schedule_line(line: String) -> (Interval, Interval) {
+    let (s_1, e_1, s_2, e_2) = line
+        .splitn(4, &['-', ','])
+        .map(|str_val| str_val.parse::<i64>().expect("Valid input guaranteed"))
         .next_tuple()
-        .expect("Valid input guaranteed")
+        .expect("Valid input guaranteed");
+
+    (Interval::new(s_1, e_1), Interval::new(s_2, e_2))
 }
 
-pub fn problem_1<I>(input_lines: I) -> impl Display
+/// Number of lines where one elf's sections hold all of the other's
+fn count_containing<I>(input_lines: I) -> usize
 where
     I: Iterator<Item = String>,
 {
     input_lines
         .map(parse_schedule_line)
-        .filter_map(|(s_1, e_1, s_2, e_2)| {
-            match (s_1 > s_2, s_1 < s_2, e_1 > e_2, e_1 < e_2) {
-                (true, _, true, _) | (_, true, _, true) => None,
-                _ => Some(1),
-            }
+        .filter(|(first, second)| {
+            first.contains_interval(second) || second.contains_interval(first)
         })
-        .sum::<i32>()
+        .count()
+}
+
+pub fn problem_1<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    // Only the report needs the whole schedule at once
+    if is_report() {
+        let input_lines = input_lines.collect_vec();
+
+        println!();
+        print!("{}", Roster::new(input_lines.iter().cloned()));
+
+        count_containing(input_lines.into_iter())
+    } else {
+        count_containing(input_lines)
+    }
 }
 
 /**** Problem 2 ******/
@@ -34,8 +55,191 @@ where
 {
     input_lines
         .map(parse_schedule_line)
-        .filter_map(|(s_1, e_1, s_2, e_2)| {
-            (!(e_1 < s_2 || e_2 < s_1)).then_some(1)
-        })
-        .sum::<i32>()
+        .filter(|(first, second)| first.overlaps(second))
+        .count()
+}
+
+/**** Roster ******/
+
+/// One of the two elves on a line of the schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Elf {
+    /// Line of the schedule, starting at 1
+    line: usize,
+
+    /// 0 for the first elf on the line and 1 for the second
+    slot: usize,
+    sections: Interval,
+}
+
+impl Display for Elf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{} ({})",
+            self.line,
+            ['a', 'b'][self.slot],
+            self.sections
+        )
+    }
+}
+
+/// Analysis of every assignment in the schedule together rather than a line
+/// at a time
+#[derive(Debug)]
+struct Roster {
+    elves: Vec<Elf>,
+
+    /// Sections assigned to the most elves, along with how many elves that is
+    busiest: Option<(Interval, usize)>,
+
+    /// How the two elves on each line relate
+    lines: Vec<Relation>,
+
+    /// Every pair of elves whose sections overlap, as indexes into `elves`
+    /// with the earlier elf first
+    overlapping: Vec<(usize, usize, Relation)>,
+}
+
+impl Roster {
+    fn new<I>(input_lines: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let pairs = input_lines.map(parse_schedule_line).collect_vec();
+
+        let lines = pairs
+            .iter()
+            .map(|(first, second)| first.relation(second))
+            .collect_vec();
+
+        let elves = pairs
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, (first, second))| {
+                [first, second].into_iter().enumerate().map(
+                    move |(slot, sections)| Elf {
+                        line: i + 1,
+                        slot,
+                        sections,
+                    },
+                )
+            })
+            .collect_vec();
+
+        let index = elves
+            .iter()
+            .enumerate()
+            .map(|(i, elf)| (elf.sections, i))
+            .collect::<IntervalIndex<_>>();
+
+        let overlapping = elves
+            .iter()
+            .enumerate()
+            .flat_map(|(i, elf)| {
+                index
+                    .overlapping(&elf.sections)
+                    .into_iter()
+                    .filter(move |(_, j)| *j > i)
+                    .map(move |(other, j)| {
+                        (i, *j, elf.sections.relation(other))
+                    })
+            })
+            .sorted_by_key(|(i, j, _)| (*i, *j))
+            .collect_vec();
+
+        Self {
+            busiest: busiest_sections(&elves),
+            elves,
+            lines,
+            overlapping,
+        }
+    }
+
+    /// Overlapping pairs where one elf's sections hold all of the other's
+    fn containing(&self) -> impl Iterator<Item = &(usize, usize, Relation)> {
+        self.overlapping
+            .iter()
+            .filter(|(_, _, relation)| relation.is_containment())
+    }
+}
+
+/// First run of sections covered by the most elves. Sweeps across the
+/// sections, adding elves as their assignments start and dropping them once
+/// they end
+fn busiest_sections(elves: &[Elf]) -> Option<(Interval, usize)> {
+    let changes = elves
+        .iter()
+        .flat_map(|elf| [(elf.sections.start, 1), (elf.sections.end + 1, -1)])
+        .into_group_map()
+        .into_iter()
+        .map(|(section, deltas)| (section, deltas.into_iter().sum::<i64>()))
+        .sorted()
+        .collect_vec();
+
+    let mut assigned = 0;
+    let mut best: Option<(Interval, usize)> = None;
+
+    for ((section, delta), (next, _)) in changes.iter().tuple_windows() {
+        assigned += delta;
+
+        if best.is_none_or(|(_, most)| assigned as usize > most) {
+            best = Some((Interval::new(*section, next - 1), assigned as usize));
+        }
+    }
+
+    best
+}
+
+impl Display for Roster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((sections, elves)) = self.busiest {
+            writeln!(f, "Busiest sections: {sections} with {elves} elves")?;
+        }
+
+        for (i, relation) in self.lines.iter().enumerate() {
+            writeln!(f, "Line {}: {relation:?}", i + 1)?;
+        }
+
+        for (a, b, relation) in &self.overlapping {
+            writeln!(f, "{} {relation:?} {}", self.elves[*a], self.elves[*b])?;
+        }
+
+        writeln!(
+            f,
+            "{} overlapping pairs, {} where one holds the other",
+            self.overlapping.len(),
+            self.containing().count()
+        )
+    }
+}
+
+#[test]
+fn test_roster() {
+    let example = ["2-4,6-8", "2-3,4-5", "5-7,7-9", "2-8,3-7", "6-6,4-6"];
+    let roster = Roster::new(example.map(str::to_owned).into_iter());
+
+    // Six elves are assigned section 6, and no more than five to any other
+    assert_eq!(roster.busiest, Some((Interval::point(6), 6)));
+    assert_eq!(roster.lines[1], Relation::Meets);
+    assert_eq!(roster.lines[3], Relation::Contains);
+
+    // Elves 1a and 4b share section 4, and neither holds the other
+    assert!(roster.overlapping.contains(&(0, 7, Relation::Overlaps)));
+    assert!(!roster
+        .overlapping
+        .iter()
+        .any(|(a, b, _)| (*a, *b) == (0, 1)));
+    assert_eq!(
+        roster.containing().count(),
+        roster
+            .overlapping
+            .iter()
+            .filter(|(a, b, _)| {
+                let (a, b) =
+                    (roster.elves[*a].sections, roster.elves[*b].sections);
+                a.contains_interval(&b) || b.contains_interval(&a)
+            })
+            .count()
+    );
 }
@@ -1,85 +1,189 @@
 use iter_tools::Itertools;
-use std::{fmt::Display, iter::once};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, satisfy, space0, space1, u32},
+    combinator::{map, value},
+    multi::separated_list1,
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+use std::fmt::Display;
 
+use crate::helpers::replay_steps;
+use crate::parsing::{parse_line, LineError};
+
+/// Stacks of crates, bottom first. Stack 0 is always empty, so the stack
+/// labels from the input can be used as indexes
 type Ship = Vec<Vec<u8>>;
 
+/// A crate like `[A]`, or the three spaces where a crate could be
+fn parse_crate_slot(input: &str) -> IResult<&str, Option<u8>> {
+    alt((
+        map(
+            delimited(char('['), satisfy(|c| c.is_ascii_graphic()), char(']')),
+            |c| Some(c as u8),
+        ),
+        value(None, tag("   ")),
+    ))(input)
+}
+
 // Parse a single line of the cargo-stack declaration section
-// [A]    [B]  => [(1, A), (3, B)]
-fn parse_cargo_line(line: String) -> impl Iterator<Item = (usize, u8)> {
-    line.into_bytes()
-        .into_iter()
-        .chain(once(b' ')) // IDE stripped trailing spaces off input XD
-        .tuples::<(u8, u8, u8, u8)>()
-        .enumerate()
-        .filter_map(move |(column, (c1, c2, _, _))| match (c1, c2) {
-            (b'[', cargo) => Some((column + 1, cargo)),
-            _ => None,
-        })
+// [A]     [B]  => [Some(A), None, Some(B)]
+fn parse_cargo_line(input: &str) -> IResult<&str, Vec<Option<u8>>> {
+    // IDE stripped trailing spaces off input XD, so whatever is left of them
+    // can be ignored
+    terminated(separated_list1(char(' '), parse_crate_slot), space0)(input)
 }
 
-/// Generate the contents of the ship and consume from the iterator only the
-/// lines describing the ship (not the moves)
-fn parse_ship<I>(lines: &mut I) -> Ship
-where
-    I: Iterator<Item = String>,
-{
-    // Take all the lines up until the empty line that separates setup from
-    // instructions, and reverse them because the way it is defined in the file
-    // works great visually, it's backward for what we need to allocating and
-    // populating stacks
-    let mut ship_lines = lines
-        .by_ref()
-        .take_while(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev();
-
-    // Find the number of stacks of crates by looking at the line labeling the
-    // stacks.
-    let label_line = ship_lines.next().expect("Label line guaranteed");
-    let max_stack_index = label_line
-        .into_bytes()
-        .into_iter()
-        .filter(|c| *c != b' ')
-        .last()
-        .map(|c| (c - b'0') as usize)
-        .expect("At least one stack guaranteed");
-
-    // Initialize the ship as a vec of empty vecs to accommodate all stack
-    // _indices_ (Ignore the zero column, so we can use the indexes specified
-    // in the move instructions)
-    let mut ship: Ship = vec![vec![]; max_stack_index + 1];
-
-    ship_lines
-        .into_iter()
-        .flat_map(parse_cargo_line)
-        .for_each(|(column, cargo)| ship[column].push(cargo));
-
-    ship
+/// The line under the drawing labeling each stack
+/// " 1   2   3 " => [1, 2, 3]
+fn parse_labels(input: &str) -> IResult<&str, Vec<u32>> {
+    delimited(space0, separated_list1(space1, u32), space0)(input)
+}
+
+/// Generate the contents of the ship from the lines drawing it
+fn parse_ship(lines: Vec<String>) -> Result<Ship, LineError> {
+    let label_index = lines.len().checked_sub(1).ok_or_else(|| LineError {
+        line: 1,
+        column: 1,
+        text: String::new(),
+        reason: "Missing ship drawing".into(),
+    })?;
+
+    let mut lines = lines.into_iter().enumerate().rev();
+
+    // The labels have to count up from 1, and they decide how many stacks
+    // there are
+    let (i, label_line) = lines.next().expect("🏷️");
+    let labels = parse_line(i, label_line.clone(), parse_labels)?;
+
+    if labels.iter().zip(1..).any(|(label, n)| *label != n) {
+        return Err(LineError {
+            line: label_index + 1,
+            column: 1,
+            text: label_line,
+            reason: "Stacks should be labeled 1, 2, 3, ...".into(),
+        });
+    }
+
+    let mut ship: Ship = vec![vec![]; labels.len() + 1];
+
+    // Fill from the bottom up
+    for (i, line) in lines {
+        let slots = parse_line(i, line.clone(), parse_cargo_line)?;
+
+        for (column, slot) in slots.into_iter().enumerate() {
+            let Some(cargo) = slot else { continue };
+
+            if column >= labels.len() {
+                return Err(LineError {
+                    line: i + 1,
+                    column: 4 * column + 1,
+                    text: line,
+                    reason: "Crate isn't over any stack".into(),
+                });
+            }
+
+            if ship[column + 1].len() != label_index - i - 1 {
+                return Err(LineError {
+                    line: i + 1,
+                    column: 4 * column + 1,
+                    text: line,
+                    reason: "Crate is floating in the air".into(),
+                });
+            }
+
+            ship[column + 1].push(cargo);
+        }
+    }
+
+    Ok(ship)
 }
 
 /// A struct to contain all the info we need from a single move instruction
 #[derive(Debug)]
 struct Move {
-    repeats: i32,
+    /// 1-based line of the input the move came from
+    line: usize,
+
+    repeats: usize,
     from: usize,
     to: usize,
 }
 
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.repeats, self.from, self.to)
+    }
+}
+
+impl Move {
+    /// Make sure the move can be done on the ship as it stands
+    fn check(&self, ship: &Ship) -> Result<(), LineError> {
+        let complaint = |reason: String| LineError {
+            line: self.line,
+            column: 1,
+            text: self.to_string(),
+            reason,
+        };
+
+        for stack in [self.from, self.to] {
+            if stack == 0 || stack >= ship.len() {
+                return Err(complaint(format!("There's no stack {stack}")));
+            }
+        }
+
+        if ship[self.from].len() < self.repeats {
+            return Err(complaint(format!(
+                "Stack {} only has {} crates",
+                self.from,
+                ship[self.from].len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 /// Parse a single move instruction line
-/// "move 23 from 6 to 4" => Move { repeat: 23, from: 6, to: 4 }
-fn parse_move(move_line: String) -> Move {
-    let mut cursor = move_line[5..].splitn(2, ' ');
-    let repeats = cursor
-        .next()
-        .and_then(|repeats_str| repeats_str.parse::<i32>().ok())
-        .expect("Valid input guaranteed");
-    let remainder = cursor.next().expect("Valid input guaranteed").as_bytes();
+/// "move 23 from 6 to 4" => (23, 6, 4)
+fn parse_move(input: &str) -> IResult<&str, (u32, u32, u32)> {
+    tuple((
+        preceded(tag("move "), u32),
+        preceded(tag(" from "), u32),
+        preceded(tag(" to "), u32),
+    ))(input)
+}
+
+/// Read the ship drawing and the moves that follow it
+fn read_input<I>(input_lines: I) -> Result<(Ship, Vec<Move>), LineError>
+where
+    I: Iterator<Item = String>,
+{
+    let mut lines = input_lines.enumerate();
+
+    let drawing = lines
+        .by_ref()
+        .map_while(|(_, line)| (!line.is_empty()).then_some(line))
+        .collect_vec();
+    let ship = parse_ship(drawing)?;
+
+    let moves = lines
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let (repeats, from, to) = parse_line(i, line, parse_move)?;
 
-    let from = (remainder[5] - b'0') as usize;
-    let to = (remainder[10] - b'0') as usize;
+            Ok(Move {
+                line: i + 1,
+                repeats: repeats as usize,
+                from: from as usize,
+                to: to as usize,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Move { repeats, from, to }
+    Ok((ship, moves))
 }
 
 /// Create a string with the labels of the crates on top of each stack in order
@@ -90,44 +194,288 @@ fn get_top_crates(ship: Ship) -> String {
         .collect::<String>()
 }
 
-pub fn problem_1<I>(mut input_lines: I) -> impl Display
+/**** Cranes ******/
+
+/// Something that can carry out a move. Moves are checked before they get
+/// here, so the crates being asked for are always there
+trait Crane {
+    fn lift(&self, ship: &mut Ship, m: &Move);
+}
+
+/// Moves crates one at a time
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, ship: &mut Ship, m: &Move) {
+        for _ in 0..m.repeats {
+            let cargo = ship[m.from].pop().expect("Checked above");
+            ship[m.to].push(cargo);
+        }
+    }
+}
+
+/// Moves all the crates at once, so they keep their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, ship: &mut Ship, m: &Move) {
+        LimitedCrane {
+            capacity: usize::MAX,
+        }
+        .lift(ship, m)
+    }
+}
+
+/// Moves as many crates as it can at once, keeping the order within each
+/// load. A capacity of 1 is the same as the 9000
+struct LimitedCrane {
+    capacity: usize,
+}
+
+impl Crane for LimitedCrane {
+    fn lift(&self, ship: &mut Ship, m: &Move) {
+        let mut left = m.repeats;
+
+        while left > 0 {
+            let load = left.min(self.capacity.max(1));
+            let remaining = ship[m.from].len() - load;
+            let mut to_move = ship[m.from].drain(remaining..).collect_vec();
+            ship[m.to].append(&mut to_move);
+            left -= load;
+        }
+    }
+}
+
+/// Pulls crates out from the bottom of the stack in one go and puts them on
+/// top of the destination in the same order
+struct BottomCrane;
+
+impl Crane for BottomCrane {
+    fn lift(&self, ship: &mut Ship, m: &Move) {
+        let mut to_move = ship[m.from].drain(..m.repeats).collect_vec();
+        ship[m.to].append(&mut to_move);
+    }
+}
+
+/// Run all the moves with the given crane
+fn operate<C: Crane>(
+    crane: &C,
+    mut ship: Ship,
+    moves: &[Move],
+) -> Result<Ship, LineError> {
+    for m in moves {
+        m.check(&ship)?;
+        crane.lift(&mut ship, m);
+    }
+
+    Ok(ship)
+}
+
+/// Draw the ship the same way the input does, trailing spaces trimmed
+fn draw(ship: &Ship) -> String {
+    let stacks = &ship[1..];
+    let height = stacks.iter().map(Vec::len).max().unwrap_or_default();
+
+    (0..height)
+        .rev()
+        .map(|level| {
+            stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(cargo) => format!("[{}]", *cargo as char),
+                    None => "   ".to_owned(),
+                })
+                .join(" ")
+        })
+        .chain(Some(
+            (1..=stacks.len()).map(|n| format!("{n:^3}")).join(" "),
+        ))
+        .map(|line| line.trim_end().to_owned())
+        .join("\n")
+}
+
+/// The ship after some number of moves
+#[derive(Debug)]
+struct Frame {
+    /// How many moves have been made. 0 is the starting position
+    step: usize,
+
+    /// The move that was just made
+    last_move: Option<String>,
+    drawing: String,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.last_move {
+            Some(m) => writeln!(f, "Step {}: {m}", self.step)?,
+            None => writeln!(f, "Step 0: start")?,
+        }
+
+        writeln!(f, "{}", self.drawing)
+    }
+}
+
+/// Run the moves with the given crane, drawing the ship at every step `show`
+/// picks out. A move that can't be done ends the replay with its error
+fn replay<C, F>(
+    crane: &C,
+    mut ship: Ship,
+    moves: &[Move],
+    mut show: F,
+) -> Result<Vec<Frame>, LineError>
 where
+    C: Crane,
+    F: FnMut(usize) -> bool,
+{
+    let mut frames = vec![];
+
+    if show(0) {
+        frames.push(Frame {
+            step: 0,
+            last_move: None,
+            drawing: draw(&ship),
+        });
+    }
+
+    for (step, m) in (1..).zip(moves) {
+        m.check(&ship)?;
+        crane.lift(&mut ship, m);
+
+        if show(step) {
+            frames.push(Frame {
+                step,
+                last_move: Some(m.to_string()),
+                drawing: draw(&ship),
+            });
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Top crates after running the input with the given crane. The ship is
+/// drawn along the way if `--replay` asked for it
+fn run<C, I>(crane: &C, input_lines: I) -> String
+where
+    C: Crane,
     I: Iterator<Item = String>,
 {
-    let ship = parse_ship(&mut input_lines);
-
-    let final_ship = input_lines.map(parse_move).fold(
-        ship,
-        |mut ship, Move { repeats, from, to }| {
-            for _ in 0..repeats {
-                let cargo = ship[from].pop().expect("Unexpected Noop move");
-                ship[to].push(cargo);
+    read_input(input_lines)
+        .and_then(|(ship, moves)| {
+            if let Some(steps) = replay_steps() {
+                let show = |step| steps.is_empty() || steps.contains(&step);
+
+                println!();
+                for frame in replay(crane, ship.clone(), &moves, show)? {
+                    println!("{frame}");
+                }
             }
 
-            ship
-        },
-    );
+            operate(crane, ship, &moves)
+        })
+        .map(get_top_crates)
+        .unwrap_or_else(|e| panic!("🚢 {e}"))
+}
 
-    get_top_crates(final_ship)
+pub fn problem_1<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    run(&CrateMover9000, input_lines)
 }
 
 /**** Problem 2 ******/
 
-pub fn problem_2<I>(mut input_lines: I) -> impl Display
+pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let ship = parse_ship(&mut input_lines);
-
-    let final_ship = input_lines.map(parse_move).fold(
-        ship,
-        |mut ship, Move { repeats, from, to }| {
-            let remaining = ship[from].len() - repeats as usize;
-            let mut to_move = ship[from].drain(remaining..).collect::<Vec<_>>();
-            ship[to].append(&mut to_move);
-            ship
-        },
+    run(&CrateMover9001, input_lines)
+}
+
+#[test]
+fn test_read_input() {
+    let lines = |text: &str| text.lines().map(str::to_owned).collect_vec();
+
+    // Eleven stacks, with the trailing spaces trimmed off
+    let drawing = [
+        "                                        [K]",
+        "[A]                                     [L]",
+        " 1   2   3   4   5   6   7   8   9  10  11",
+        "",
+        "move 2 from 11 to 10",
+    ]
+    .join("\n");
+
+    let (ship, moves) = read_input(lines(&drawing).into_iter()).expect("🚢");
+    assert_eq!(ship.len(), 12);
+    assert_eq!(ship[11], b"LK");
+    assert_eq!((moves[0].line, moves[0].from, moves[0].to), (5, 11, 10));
+    assert!(moves[0].check(&ship).is_ok());
+
+    let too_many = Move {
+        line: 5,
+        repeats: 3,
+        from: 11,
+        to: 1,
+    };
+    assert_eq!(
+        too_many.check(&ship).map_err(|e| e.reason),
+        Err("Stack 11 only has 2 crates".to_owned())
     );
 
-    get_top_crates(final_ship)
+    let error =
+        read_input(lines("[A]\n 1\n\nmove 1 from 1 to 1\nmove x").into_iter())
+            .expect_err("Bad move");
+    assert_eq!((error.line, error.column), (5, 6));
+
+    let error = read_input(lines("[A] [B]\n 1").into_iter()).expect_err("🪂");
+    assert_eq!(error.reason, "Crate isn't over any stack");
+}
+
+#[test]
+fn test_cranes_and_replay() {
+    let input = [
+        "    [D]",
+        "[N] [C]",
+        "[Z] [M] [P]",
+        " 1   2   3",
+        "",
+        "move 1 from 2 to 1",
+        "move 3 from 1 to 3",
+    ];
+    let (ship, moves) =
+        read_input(input.into_iter().map(str::to_owned)).expect("🚢");
+
+    // The drawing should come back out the way it went in
+    assert_eq!(draw(&ship), input[..4].join("\n"));
+
+    // Everything ends up on stack 3, in an order that depends on the crane
+    let stack_3 = |crane: &dyn Crane| {
+        let mut ship = ship.clone();
+        moves.iter().for_each(|m| crane.lift(&mut ship, m));
+        String::from_utf8(ship[3].clone()).expect("🏗️")
+    };
+    assert_eq!(stack_3(&CrateMover9000), "PDNZ");
+    assert_eq!(stack_3(&CrateMover9001), "PZND");
+    assert_eq!(stack_3(&LimitedCrane { capacity: 2 }), "PNDZ");
+    assert_eq!(stack_3(&BottomCrane), "PZNM");
+
+    let frames =
+        replay(&CrateMover9000, ship.clone(), &moves, |step| step != 1)
+            .expect("🎞️");
+    assert_eq!(frames.iter().map(|f| f.step).collect_vec(), vec![0, 2]);
+    assert_eq!(
+        frames[1].to_string(),
+        [
+            "Step 2: move 3 from 1 to 3",
+            "        [Z]",
+            "        [N]",
+            "    [C] [D]",
+            "    [M] [P]",
+            " 1   2   3\n",
+        ]
+        .join("\n")
+    );
 }
@@ -1,92 +1,247 @@
-use std::fmt::Display;
-
-/// Convert and ascii byte into an indexed letter starting at one.
-/// Ex: (a -> 1, b -> 2 ... z -> 26)
-///
-/// We leave 0 as an indicator of an invalid letter
-fn byte_to_letter(byte: u8) -> usize {
-    (byte - b'a' + 1) as usize
-}
+use std::{
+    fmt::Display,
+    io::{self, ErrorKind, Read},
+    iter::once,
+};
+
+use iter_tools::Itertools;
+
+use crate::helpers::is_report;
+
+/// Number of unique bytes that mark the start of a packet
+const PACKET_MARKER_LEN: usize = 4;
+
+/// Number of unique bytes that mark the start of a message
+const MESSAGE_MARKER_LEN: usize = 14;
+
+/// How much of the stream gets read at a time
+const CHUNK_SIZE: usize = 4096;
 
 /// Over-engineered structure for capturing a rolling frame of data from a
-/// stream and detecting a frame that is made up of unique letters
-#[derive(Default)]
-struct UniqueSequenceFinder<const L: usize>
-where
-    [u8; L]: Default,
-{
-    buffer: [u8; L],
-    letters_consumed: usize,
-    letter_counts: [i32; 26 + 1],
-    overflow_count: i32,
+/// stream and detecting a frame that is made up of unique bytes
+struct UniqueSequenceFinder {
+    buffer: Vec<u8>,
+    bytes_consumed: usize,
+
+    /// Bytes in the frame since it was last cleared, up to its length
+    filled: usize,
+    byte_counts: [u32; 256],
+    overflow_count: u32,
 }
 
-impl<const L: usize> UniqueSequenceFinder<L>
-where
-    [u8; L]: Default,
-{
-    /// Branchless method for progressing the stream one letter and checking
-    /// for a full frame of unique letters
-    fn append_and_detect(&mut self, in_letter: usize) -> bool {
-        let cursor = self.letters_consumed % L;
-        let out_letter = self.buffer[cursor] as usize;
+impl UniqueSequenceFinder {
+    /// Finder for frames of `len` bytes
+    fn new(len: usize) -> Self {
+        assert!(len > 0, "Markers need at least one byte");
+
+        Self {
+            buffer: vec![0; len],
+            bytes_consumed: 0,
+            filled: 0,
+            byte_counts: [0; 256],
+            overflow_count: 0,
+        }
+    }
+
+    /// Progress the stream one byte and check for a full frame of unique
+    /// bytes
+    fn append_and_detect(&mut self, in_byte: u8) -> bool {
+        let len = self.buffer.len();
+        let cursor = self.bytes_consumed % len;
 
-        // Decrement and check if removing the old letter removes a duplicate
-        self.letter_counts[out_letter] -= 1;
-        let duplicate_removed = self.letter_counts[out_letter] > 0;
+        // Nothing leaves the frame until it has filled up
+        if self.filled == len {
+            let out_byte = self.buffer[cursor] as usize;
 
-        // Reduce the number of duplicates by one if a duplicate was removed
-        self.overflow_count -= (duplicate_removed && out_letter > 0) as i32;
+            // Decrement and check if removing the old byte removes a
+            // duplicate
+            self.byte_counts[out_byte] -= 1;
+            let duplicate_removed = self.byte_counts[out_byte] > 0;
+            self.overflow_count -= duplicate_removed as u32;
+        }
 
-        // Replace the removed letter with the new one
-        self.buffer[cursor] = in_letter as u8;
+        // Replace the removed byte with the new one
+        self.buffer[cursor] = in_byte;
 
-        // Increment and check to see if adding the new letter adds a duplicate
-        self.letter_counts[in_letter] += 1;
-        let duplicate_added = self.letter_counts[in_letter] > 1;
+        // Increment and check to see if adding the new byte adds a duplicate
+        self.byte_counts[in_byte as usize] += 1;
+        let duplicate_added = self.byte_counts[in_byte as usize] > 1;
 
-        // Apply the changes to the state from adding the new letter
-        self.overflow_count += duplicate_added as i32;
-        self.letters_consumed += 1;
+        // Apply the changes to the state from adding the new byte
+        self.overflow_count += duplicate_added as u32;
+        self.bytes_consumed += 1;
+        self.filled = len.min(self.filled + 1);
 
         // A unique sequence is detected if no duplicates are detected after
         // filling the buffer
-        self.letters_consumed >= L && self.overflow_count == 0
+        self.filled == len && self.overflow_count == 0
+    }
+
+    /// Progress the stream past a byte that ends the frame rather than
+    /// joining it
+    fn clear(&mut self) {
+        self.bytes_consumed += 1;
+        self.filled = 0;
+        self.byte_counts = [0; 256];
+        self.overflow_count = 0;
+    }
+}
+
+/// Every position in a stream where the last few bytes were all different.
+/// Positions count the bytes read so far, so they're the puzzle's answers.
+/// Each line is its own signal, so markers never take in a newline
+struct Markers<R> {
+    reader: R,
+    finder: UniqueSequenceFinder,
+    chunk: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.start < self.end {
+                let byte = self.chunk[self.start];
+                self.start += 1;
+
+                if byte == b'\n' {
+                    self.finder.clear();
+                } else if self.finder.append_and_detect(byte) {
+                    return Some(Ok(self.finder.bytes_consumed));
+                }
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => return None,
+                Ok(read) => (self.start, self.end) = (0, read),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Find markers of `len` unique bytes in the stream, reading it a chunk at a
+/// time
+fn markers<R: Read>(reader: R, len: usize) -> Markers<R> {
+    Markers {
+        reader,
+        finder: UniqueSequenceFinder::new(len),
+        chunk: vec![0; CHUNK_SIZE],
+        start: 0,
+        end: 0,
     }
 }
 
-pub fn problem_1<I>(mut input_lines: I) -> impl Display
+/// Stream over the input lines, with each line ending in a newline. The
+/// runner still hands over a line at a time, but only one of them is held
+/// here at once
+struct LineReader<I> {
+    lines: I,
+    line: Vec<u8>,
+    read: usize,
+}
+
+impl<I> LineReader<I> {
+    fn new(lines: I) -> Self {
+        Self {
+            lines,
+            line: vec![],
+            read: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Read for LineReader<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.read == self.line.len() {
+            let Some(line) = self.lines.next() else {
+                return Ok(0);
+            };
+
+            self.line = line.into_bytes();
+            self.line.push(b'\n');
+            self.read = 0;
+        }
+
+        let count = buf.len().min(self.line.len() - self.read);
+        buf[..count].copy_from_slice(&self.line[self.read..][..count]);
+        self.read += count;
+
+        Ok(count)
+    }
+}
+
+/// Position of the first marker in the signal. Every other position gets
+/// listed too if a report was asked for
+fn first_marker<I>(input_lines: I, len: usize) -> usize
 where
     I: Iterator<Item = String>,
 {
-    let mut detector = UniqueSequenceFinder::<4>::default();
-
-    input_lines
-        .next()
-        .expect("One line guaranteed")
-        .into_bytes()
-        .into_iter()
-        .map(byte_to_letter)
-        .take_while(move |c| !detector.append_and_detect(*c))
-        .count()
-        + 1
+    let mut found = markers(LineReader::new(input_lines), len)
+        .map(|position| position.expect("Reading from the input lines"));
+    let first = found.next().expect("📡");
+
+    if is_report() {
+        println!();
+        println!("Markers at {}", once(first).chain(found).join(", "));
+    }
+
+    first
+}
+
+pub fn problem_1<I>(input_lines: I) -> impl Display
+where
+    I: Iterator<Item = String>,
+{
+    first_marker(input_lines, PACKET_MARKER_LEN)
 }
 
 /**** Problem 2 ******/
 
-pub fn problem_2<I>(mut input_lines: I) -> impl Display
+pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut detector = UniqueSequenceFinder::<14>::default();
-
-    input_lines
-        .next()
-        .expect("One line guaranteed")
-        .into_bytes()
-        .into_iter()
-        .map(byte_to_letter)
-        .take_while(move |c| !detector.append_and_detect(*c))
-        .count()
-        + 1
+    first_marker(input_lines, MESSAGE_MARKER_LEN)
+}
+
+#[test]
+fn test_markers() {
+    let all = |signal: &[u8], len| {
+        markers(signal, len)
+            .collect::<io::Result<Vec<_>>>()
+            .expect("📡")
+    };
+
+    assert_eq!(all(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4)[0], 7);
+    assert_eq!(all(b"aabab", 2), vec![3, 4, 5]);
+    assert_eq!(all(b"\x00\xff\x00 \xff", 3), vec![4, 5]);
+    assert_eq!(all(b"ab\ncd", 2), vec![2, 5]);
+
+    // Markers still show up across chunk boundaries
+    let signal = b"xxy".repeat(CHUNK_SIZE);
+    let found = all(&signal, 2);
+    assert_eq!(found.len(), 2 * CHUNK_SIZE - 1);
+    assert_eq!(found[..3], [3, 4, 6]);
+}
+
+#[test]
+fn test_line_reader() {
+    let lines = |lines: [&str; 2]| lines.map(str::to_owned).into_iter();
+
+    let mut read = String::new();
+    LineReader::new(lines(["ab", "cde"]))
+        .read_to_string(&mut read)
+        .expect("📡");
+    assert_eq!(read, "ab\ncde\n");
+
+    // "bc\nd" would be unique, but markers stop at the end of a line
+    let found = markers(LineReader::new(lines(["abc", "abcd"])), 4)
+        .collect::<io::Result<Vec<_>>>()
+        .expect("📡");
+    assert_eq!(found, vec![8]);
 }
@@ -2,117 +2,503 @@ use iter_tools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, char, not_line_ending, u32},
-    combinator::{map, recognize, value},
-    multi::separated_list0,
-    sequence::{preceded, terminated},
+    character::complete::{char, not_line_ending, space0, u64},
+    combinator::{all_consuming, eof, map, value, verify},
+    sequence::{pair, preceded, separated_pair, terminated},
     IResult,
 };
 use std::{collections::BTreeMap, fmt::Display};
 
-/// All the operations done while traversing the file system
-#[derive(Debug, Clone)]
-enum Operation {
-    /// `cd ..`
-    CdUp,
+/// Total space on the device
+const DISK_SIZE: u64 = 70_000_000;
 
-    /// `cd` to the contained relative path (One path component)
-    CdDown(String),
+/// Free space the update needs
+const SPACE_NEEDED: u64 = 30_000_000;
 
-    /// List the contents, but we only keep the total size of all files listed
-    Ls(u32),
+/// Biggest directory that counts for problem 1
+const SMALL_DIR_LIMIT: u64 = 100_000;
+
+/// Something shown by `ls`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Listing {
+    Dir(String),
+    File(String, u64),
+}
+
+/// A single line of the transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// `$ cd` to a relative or absolute path
+    Cd(String),
+
+    /// `$ ls` of the current directory
+    Ls,
+
+    /// Any other command, which we don't know how to replay
+    Unknown(String),
+
+    /// Output from `ls`
+    Listed(Listing),
+}
+
+/// Something wrong with the transcript. Lines are numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Warning {
+    Unparsable {
+        line: usize,
+        text: String,
+    },
+    UnknownCommand {
+        line: usize,
+        command: String,
+    },
+    StrayOutput {
+        line: usize,
+        text: String,
+    },
+    AboveRoot {
+        line: usize,
+    },
+    NotADirectory {
+        line: usize,
+        path: String,
+    },
+    Unlisted {
+        line: usize,
+        path: String,
+    },
+    Resized {
+        line: usize,
+        path: String,
+        old: u64,
+        new: u64,
+    },
+    KindChanged {
+        line: usize,
+        path: String,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Warning::*;
+
+        match self {
+            Unparsable { line, text } => {
+                write!(f, "line {line}: can't make sense of {text:?}")
+            }
+            UnknownCommand { line, command } => {
+                write!(f, "line {line}: skipping unknown command {command:?}")
+            }
+            StrayOutput { line, text } => {
+                write!(f, "line {line}: {text:?} isn't output from `ls`")
+            }
+            AboveRoot { line } => {
+                write!(f, "line {line}: can't go above /, staying there")
+            }
+            NotADirectory { line, path } => {
+                write!(f, "line {line}: {path} isn't a directory")
+            }
+            Unlisted { line, path } => {
+                write!(f, "line {line}: {path} was never listed")
+            }
+            Resized {
+                line,
+                path,
+                old,
+                new,
+            } => write!(f, "line {line}: {path} went from {old} to {new}"),
+            KindChanged { line, path } => write!(
+                f,
+                "line {line}: {path} was listed as a file and a directory"
+            ),
+        }
+    }
 }
 
 /********************* Awesome Nom Parsing **********************/
 
-/// Consume a line showing a directory in the `ls` output
-fn parse_listed_directory(input: &str) -> IResult<&'_ str, u32> {
-    value(0, recognize(preceded(tag("dir "), alpha1)))(input)
+/// Any name at all, as long as there is one
+fn parse_name(input: &str) -> IResult<&'_ str, &'_ str> {
+    verify(not_line_ending, |name: &str| !name.is_empty())(input)
+}
+
+/// Name of something `ls` shows. It has to be something `cd` can get to, so
+/// no slashes, and not `.` or `..`
+fn parse_entry_name(input: &str) -> IResult<&'_ str, &'_ str> {
+    verify(parse_name, |name: &str| {
+        !name.contains('/') && name != "." && name != ".."
+    })(input)
+}
+
+/// Parse a line showing a directory in the `ls` output
+fn parse_listed_directory(input: &str) -> IResult<&'_ str, Listing> {
+    map(preceded(tag("dir "), parse_entry_name), |name: &str| {
+        Listing::Dir(name.to_owned())
+    })(input)
 }
 
 /// Parse a line showing a file entry in the `ls` output
-fn parse_listed_file(input: &str) -> IResult<&'_ str, u32> {
-    terminated(u32, not_line_ending)(input)
+fn parse_listed_file(input: &str) -> IResult<&'_ str, Listing> {
+    map(
+        separated_pair(u64, char(' '), parse_entry_name),
+        |(size, name): (u64, &str)| Listing::File(name.to_owned(), size),
+    )(input)
 }
 
 /// Parse either a file or directory in the `ls` output
-fn parse_ls_entry(input: &str) -> IResult<&'_ str, u32> {
+fn parse_ls_entry(input: &str) -> IResult<&'_ str, Listing> {
     alt((parse_listed_directory, parse_listed_file))(input)
 }
 
-/// Parse the entire `ls` command and output
-fn parse_ls(command_and_response: &str) -> IResult<&'_ str, Operation> {
-    map(
-        preceded(tag("ls\n"), separated_list0(char('\n'), parse_ls_entry)),
-        |listings| Operation::Ls(listings.iter().sum::<u32>()),
-    )(command_and_response)
+/// Parse a command line of any type
+fn parse_command(command_line: &str) -> IResult<&'_ str, Line> {
+    preceded(
+        tag("$ "),
+        alt((
+            map(preceded(tag("cd "), parse_name), |path: &str| {
+                Line::Cd(path.to_owned())
+            }),
+            value(Line::Ls, terminated(tag("ls"), pair(space0, eof))),
+            map(not_line_ending, |command: &str| {
+                Line::Unknown(command.to_owned())
+            }),
+        )),
+    )(command_line)
 }
 
-/// Parse a `cd` command of any type
-fn parse_cd(command_line: &str) -> IResult<&'_ str, Operation> {
-    alt((
-        value(Operation::CdUp, tag("cd ..")),
-        map(preceded(tag("cd "), not_line_ending), |dir: &str| {
-            Operation::CdDown(dir.to_owned())
-        }),
-    ))(command_line)
+/// Parse a line of the transcript, if it makes any sense
+fn parse_transcript_line(line: &str) -> Option<Line> {
+    all_consuming(alt((parse_command, map(parse_ls_entry, Line::Listed))))(line)
+        .ok()
+        .map(|(_, parsed)| parsed)
 }
 
-/// Parse the command and output into an operation
-fn parse_op(command_and_output: String) -> Operation {
-    preceded(tag("$ "), alt((parse_cd, parse_ls)))(&command_and_output)
-        .expect("Bad input not allowed")
-        .1
+/*************** The file system the commands explore ****************/
+
+/// Index of an entry in the file system
+type EntryId = usize;
+
+/// The root directory is always the first entry
+const ROOT: EntryId = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    /// Directory with its entries by name
+    Dir(BTreeMap<String, EntryId>),
+    File(u64),
 }
 
-/***** Walking the file system and replaying the commands *******/
+#[derive(Debug)]
+struct Entry {
+    name: String,
 
-/// Recursively walk the directory structure by evaluating the operations.
-/// Apply the given accumulator on each directory and return the total size of
-/// the current directory
-fn fs_walker<C, A>(dir: String, commands: &mut C, accumulator: &mut A) -> u32
-where
-    C: Iterator<Item = Operation>,
-    A: FnMut(String, u32),
-{
-    use Operation::*;
+    /// The root is its own parent
+    parent: EntryId,
+    kind: Kind,
+}
+
+impl Entry {
+    fn is_dir(&self) -> bool {
+        matches!(self.kind, Kind::Dir(_))
+    }
+}
+
+/// In-memory tree of everything the transcript saw. Entries are only ever
+/// added after their parent, so children always come later in `entries`
+#[derive(Debug)]
+struct FileSystem {
+    entries: Vec<Entry>,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        Self {
+            entries: vec![Entry {
+                name: "/".to_owned(),
+                parent: ROOT,
+                kind: Kind::Dir(BTreeMap::new()),
+            }],
+        }
+    }
+
+    /// Rebuild the file system by replaying the commands in the transcript,
+    /// along with everything odd found along the way
+    fn replay<I>(input_lines: I) -> (Self, Vec<Warning>)
+    where
+        I: Iterator<Item = String>,
+    {
+        use Line::*;
+
+        /// What the lines that aren't commands belong to
+        enum Reading {
+            Nothing,
+            Listing(EntryId),
+            Unknown,
+        }
+
+        let mut fs = Self::new();
+        let mut warnings = vec![];
+        let mut cwd = ROOT;
+        let mut reading = Reading::Nothing;
 
-    let mut curr_size = 0;
+        for (i, text) in input_lines.enumerate() {
+            let line = i + 1;
 
-    while let Some(command) = commands.next() {
-        match command {
-            Ls(local_file_size) => curr_size += local_file_size,
-            CdDown(target) => {
-                curr_size += fs_walker(target, commands, accumulator)
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            match (parse_transcript_line(&text), &reading) {
+                (Some(Cd(path)), _) => {
+                    reading = Reading::Nothing;
+                    cwd = fs.cd(line, cwd, &path, &mut warnings);
+                }
+                (Some(Ls), _) => reading = Reading::Listing(cwd),
+                (Some(Unknown(command)), _) => {
+                    reading = Reading::Unknown;
+                    warnings.push(Warning::UnknownCommand { line, command });
+                }
+
+                // Whatever an unknown command printed was already warned about
+                (_, Reading::Unknown) => {}
+                (Some(Listed(listing)), Reading::Listing(dir)) => {
+                    fs.record(line, *dir, listing, &mut warnings)
+                }
+                (Some(Listed(_)), Reading::Nothing) => {
+                    warnings.push(Warning::StrayOutput { line, text })
+                }
+                (None, _) => warnings.push(Warning::Unparsable { line, text }),
             }
-            CdUp => break,
         }
+
+        (fs, warnings)
     }
 
-    accumulator(dir, curr_size);
+    /// Entry with the given name in the directory
+    fn child(&self, dir: EntryId, name: &str) -> Option<EntryId> {
+        match &self.entries[dir].kind {
+            Kind::Dir(children) => children.get(name).copied(),
+            Kind::File(_) => None,
+        }
+    }
 
-    curr_size
-}
+    /// Change directory from `cwd` by following the path, making any
+    /// directories along the way that were never listed
+    fn cd(
+        &mut self,
+        line: usize,
+        cwd: EntryId,
+        path: &str,
+        warnings: &mut Vec<Warning>,
+    ) -> EntryId {
+        let mut curr = if path.starts_with('/') { ROOT } else { cwd };
+
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." if curr == ROOT => {
+                    warnings.push(Warning::AboveRoot { line })
+                }
+                ".." => curr = self.entries[curr].parent,
+                name => match self.child(curr, name) {
+                    Some(id) if self.entries[id].is_dir() => curr = id,
+                    Some(id) => {
+                        let path = self.path(id);
+                        warnings.push(Warning::NotADirectory { line, path });
+                        return cwd;
+                    }
+                    None => {
+                        let dir = Kind::Dir(BTreeMap::new());
+                        curr = self.add(curr, name.to_owned(), dir);
+                        let path = self.path(curr);
+                        warnings.push(Warning::Unlisted { line, path });
+                    }
+                },
+            }
+        }
+
+        curr
+    }
+
+    /// Add something `ls` showed in the directory. Listing the same thing
+    /// again doesn't change anything unless it looks different this time
+    fn record(
+        &mut self,
+        line: usize,
+        dir: EntryId,
+        listing: Listing,
+        warnings: &mut Vec<Warning>,
+    ) {
+        let (name, kind) = match listing {
+            Listing::Dir(name) => (name, Kind::Dir(BTreeMap::new())),
+            Listing::File(name, size) => (name, Kind::File(size)),
+        };
+
+        let Some(id) = self.child(dir, &name) else {
+            self.add(dir, name, kind);
+            return;
+        };
 
-/// Convenience macro to process the raw input lines and convert them to ops,
-/// and run the given accumulator. The macro will return the total size of all
-/// files in the file system
-macro_rules! walk_directories {
-    ($input_lines:ident, $accumulator:expr) => {
-        {
-            let mut command_num = 0;
-            let command_groups = $input_lines.group_by(move |line| {
-                line.starts_with('$').then(|| command_num += 1);
-                command_num
+        match (&mut self.entries[id].kind, kind) {
+            // Trust the latest listing
+            (Kind::File(old), Kind::File(new)) if *old != new => {
+                let old = std::mem::replace(old, new);
+                let path = self.path(id);
+                warnings.push(Warning::Resized {
+                    line,
+                    path,
+                    old,
+                    new,
+                });
+            }
+            (Kind::Dir(_), Kind::File(_)) | (Kind::File(_), Kind::Dir(_)) => {
+                let path = self.path(id);
+                warnings.push(Warning::KindChanged { line, path });
+            }
+            _ => {}
+        }
+    }
+
+    /// Add an entry to the given directory, or get the one that's already
+    /// there with that name
+    fn add(&mut self, dir: EntryId, name: String, kind: Kind) -> EntryId {
+        let next_id = self.entries.len();
+
+        let Kind::Dir(children) = &mut self.entries[dir].kind else {
+            panic!("📄 {} isn't a directory", self.path(dir));
+        };
+
+        let id = *children.entry(name.clone()).or_insert(next_id);
+
+        if id == next_id {
+            self.entries.push(Entry {
+                name,
+                parent: dir,
+                kind,
             });
+        }
+
+        id
+    }
+
+    /// Follow a path from the given directory. Absolute paths start at the
+    /// root, and `.` and `..` work like they do in a shell
+    fn lookup_from(&self, dir: EntryId, path: &str) -> Option<EntryId> {
+        let start = if path.starts_with('/') { ROOT } else { dir };
+
+        path.split('/').try_fold(start, |id, part| match part {
+            "" | "." => Some(id),
+            ".." => Some(self.entries[id].parent),
+            name => self.child(id, name),
+        })
+    }
+
+    /// Find the entry at the given absolute path
+    fn lookup(&self, path: &str) -> Option<EntryId> {
+        self.lookup_from(ROOT, path)
+    }
+
+    /// Absolute path of the entry
+    fn path(&self, id: EntryId) -> String {
+        let mut names = vec![];
+        let mut curr = id;
+
+        while curr != ROOT {
+            names.push(self.entries[curr].name.as_str());
+            curr = self.entries[curr].parent;
+        }
+
+        format!("/{}", names.into_iter().rev().join("/"))
+    }
+
+    /// Size of every entry, with directories holding the total of everything
+    /// under them
+    fn sizes(&self) -> Vec<u64> {
+        let mut sizes = self
+            .entries
+            .iter()
+            .map(|entry| match entry.kind {
+                Kind::File(size) => size,
+                Kind::Dir(_) => 0,
+            })
+            .collect_vec();
+
+        // Children come after their parents, so going backwards finishes each
+        // directory before it gets added to its parent
+        for id in (1..self.entries.len()).rev() {
+            sizes[self.entries[id].parent] += sizes[id];
+        }
+
+        sizes
+    }
+
+    /// Total size of everything under the entry
+    fn du(&self, id: EntryId) -> u64 {
+        match &self.entries[id].kind {
+            Kind::File(size) => *size,
+            Kind::Dir(children) => children.values().map(|c| self.du(*c)).sum(),
+        }
+    }
+
+    /// Every entry the predicate picks out, given the entry and its size, with
+    /// its size
+    fn find<F>(&self, mut matches: F) -> Vec<(EntryId, u64)>
+    where
+        F: FnMut(&Entry, u64) -> bool,
+    {
+        self.sizes()
+            .into_iter()
+            .enumerate()
+            .filter(|(id, size)| matches(&self.entries[*id], *size))
+            .collect()
+    }
 
-            let mut commands = command_groups
-                .into_iter()
-                .map(|(_, mut command_lines)| parse_op(command_lines.join("\n")));
+    /// Print the entry and everything under it like `tree` would
+    fn write_tree(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        id: EntryId,
+        sizes: &[u64],
+        depth: usize,
+    ) -> std::fmt::Result {
+        let entry = &self.entries[id];
+        let indent = "  ".repeat(depth);
 
-            fs_walker(String::new(), &mut commands, &mut $accumulator)
+        match &entry.kind {
+            Kind::File(size) => {
+                writeln!(f, "{indent}- {} (file, size={size})", entry.name)
+            }
+            Kind::Dir(children) => {
+                writeln!(
+                    f,
+                    "{indent}- {} (dir, size={})",
+                    entry.name, sizes[id]
+                )?;
+                children
+                    .values()
+                    .try_for_each(|c| self.write_tree(f, *c, sizes, depth + 1))
+            }
         }
-    };
+    }
+}
+
+impl Display for FileSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_tree(f, ROOT, &self.sizes(), 0)
+    }
+}
+
+/// Replay the transcript, pointing out anything odd in it
+fn explore<I>(input_lines: I) -> FileSystem
+where
+    I: Iterator<Item = String>,
+{
+    let (fs, warnings) = FileSystem::replay(input_lines);
+    warnings
+        .iter()
+        .for_each(|warning| eprintln!("⚠️ {warning}"));
+    fs
 }
 
 /**** Problem 1 ******/
@@ -121,11 +507,11 @@ pub fn problem_1<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut result = 0;
-    walk_directories!(input_lines, |dir, size| {
-        (size <= 100_000).then(|| result += size);
-    });
-    result
+    explore(input_lines)
+        .find(|entry, size| entry.is_dir() && size <= SMALL_DIR_LIMIT)
+        .into_iter()
+        .map(|(_, size)| size)
+        .sum::<u64>()
 }
 
 /**** Problem 2 ******/
@@ -134,18 +520,133 @@ pub fn problem_2<I>(input_lines: I) -> impl Display
 where
     I: Iterator<Item = String>,
 {
-    let mut sizes = BTreeMap::new();
-    let total_size = walk_directories!(input_lines, |dir, size| {
-        sizes.insert(size, dir);
-    });
+    let fs = explore(input_lines);
 
-    let free_space = 70_000_000 - total_size;
-    let minimum_file_size = 30_000_000 - free_space;
+    // A transcript that adds up to more than the disk just has no space free
+    let free_space = DISK_SIZE.saturating_sub(fs.du(ROOT));
+    let minimum_file_size = SPACE_NEEDED.saturating_sub(free_space);
 
-    sizes
-        .split_off(&minimum_file_size)
+    fs.find(|entry, size| entry.is_dir() && size >= minimum_file_size)
         .into_iter()
-        .next()
+        .map(|(_, size)| size)
+        .min()
         .expect("A valid answer should be in this range")
-        .0
+}
+
+#[test]
+fn test_file_system() {
+    let (fs, warnings) = FileSystem::replay(
+        crate::helpers::read_lines("input/day_7_example.txt")
+            .expect("📁")
+            .map_while(Result::ok),
+    );
+    assert_eq!(warnings, vec![]);
+
+    let e = fs.lookup("/a/e").expect("📁");
+    assert_eq!(fs.path(e), "/a/e");
+    assert_eq!(fs.du(e), 584);
+    assert_eq!(fs.lookup_from(e, "../../d/k"), fs.lookup("/d/k"));
+    assert_eq!(fs.lookup("/a/nope"), None);
+
+    let logs = fs.find(|entry, _| entry.name.ends_with(".log"));
+    assert_eq!(logs, vec![(fs.lookup("/d/d.log").expect("📄"), 8033020)]);
+
+    let tree = fs.to_string();
+    assert!(
+        tree.starts_with("- / (dir, size=48381165)\n  - a (dir, size=94853)")
+    );
+    assert!(tree.contains("\n      - i (file, size=584)\n"));
+}
+
+#[test]
+fn test_messy_transcript() {
+    use Warning::*;
+
+    let transcript = [
+        "$ cd /",
+        "$ ls",
+        "dir v1.2",
+        "10 notes",
+        "$ cd v1.2",
+        "$ ls",
+        "100 a.out",
+        "$ ls",
+        "100 a.out",
+        "$ cd /v1.2/../../..",
+        "$ ls",
+        "dir v1.2",
+        "12 notes",
+        "dir notes",
+        "$ cd /notes",
+        "$ pwd",
+        "/",
+        "$ cd /new/404",
+        "$ cd ..",
+        "what",
+        "$ ls",
+        "dir a/b",
+        "1 ..",
+    ];
+
+    let (fs, warnings) =
+        FileSystem::replay(transcript.into_iter().map(str::to_owned));
+
+    // Listing v1.2 twice doesn't count a.out twice
+    assert_eq!(fs.du(ROOT), 112);
+    assert_eq!(fs.lookup("/v1.2/a.out").map(|id| fs.du(id)), Some(100));
+    assert!(fs.lookup("/new/404").is_some());
+    assert_eq!(
+        warnings,
+        vec![
+            AboveRoot { line: 10 },
+            AboveRoot { line: 10 },
+            Resized {
+                line: 13,
+                path: "/notes".to_owned(),
+                old: 10,
+                new: 12
+            },
+            KindChanged {
+                line: 14,
+                path: "/notes".to_owned()
+            },
+            NotADirectory {
+                line: 15,
+                path: "/notes".to_owned()
+            },
+            UnknownCommand {
+                line: 16,
+                command: "pwd".to_owned()
+            },
+            Unlisted {
+                line: 18,
+                path: "/new".to_owned()
+            },
+            Unlisted {
+                line: 18,
+                path: "/new/404".to_owned()
+            },
+            Unparsable {
+                line: 20,
+                text: "what".to_owned()
+            },
+            Unparsable {
+                line: 22,
+                text: "dir a/b".to_owned()
+            },
+            Unparsable {
+                line: 23,
+                text: "1 ..".to_owned()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_overfull_disk() {
+    let transcript =
+        ["$ ls", "80000000 huge", "dir a", "$ cd a", "$ ls", "5 b"];
+    let lines = || transcript.into_iter().map(str::to_owned);
+
+    assert_eq!(problem_2(lines()).to_string(), "80000005");
 }
@@ -8,8 +8,14 @@ use nom::{
     IResult,
 };
 
+use crate::geometry::{Dir4, Point2};
+
 const RENDERING_ENABLED: bool = false;
 
+/// A knot's most recent movement paired with its new position. Clippy found
+/// the bare tuples a bit much
+type KnotMove = (Point2, Point2);
+
 /// Frivolous structure for drawing the state of the world for debugging
 struct Canvas {
     contents: Vec<char>,
@@ -122,18 +128,20 @@ impl Canvas {
     }
 }
 
-/// Parse a line of instructions into a direction tuple and a number of repeats
-fn parse_move_line(line: String) -> (u32, (i32, i32)) {
+/// Parse a line of instructions into a direction and a number of repeats
+fn parse_move_line(line: String) -> (u32, Point2) {
     let result: IResult<&str, (char, u32)> =
         separated_pair(anychar, many1(char(' ')), u32)(&line);
 
-    match result.expect("Valid input brah").1 {
-        ('U', count) => (count, (0, -1)),
-        ('D', count) => (count, (0, 1)),
-        ('L', count) => (count, (-1, 0)),
-        ('R', count) => (count, (1, 0)),
+    let (dir, count) = match result.expect("Valid input brah").1 {
+        ('U', count) => (Dir4::N, count),
+        ('D', count) => (Dir4::S, count),
+        ('L', count) => (Dir4::W, count),
+        ('R', count) => (Dir4::E, count),
         _ => unreachable!("Brah, do you even validate?"),
-    }
+    };
+
+    (count, dir.delta())
 }
 
 /// Create a closure that can be used in a `map` operation simulate the direct
@@ -142,15 +150,14 @@ fn parse_move_line(line: String) -> (u32, (i32, i32)) {
 fn evaluate_moves(
     id: char,
     canvas: Rc<RefCell<Canvas>>,
-) -> impl FnMut((i32, i32)) -> ((i32, i32), (i32, i32)) {
-    let mut curr = (0, 0);
+) -> impl FnMut(Point2) -> KnotMove {
+    let mut curr = Point2::ORIGIN;
     move |delta| {
         let prev = curr;
 
-        curr.0 += delta.0;
-        curr.1 += delta.1;
+        curr += delta;
 
-        canvas.borrow_mut().move_entry(id, prev, curr);
+        canvas.borrow_mut().move_entry(id, prev.into(), curr.into());
 
         (delta, curr)
     }
@@ -164,28 +171,29 @@ fn evaluate_moves(
 fn follow_head(
     id: char,
     canvas: Rc<RefCell<Canvas>>,
-) -> impl FnMut(((i32, i32), (i32, i32))) -> ((i32, i32), (i32, i32)) {
-    let mut offset_to_head = (0, 0);
-    let mut curr = (0, 0);
+) -> impl FnMut(KnotMove) -> KnotMove {
+    let mut offset_to_head = Point2::ORIGIN;
+    let mut curr = Point2::ORIGIN;
 
     move |(delta, head)| {
-        offset_to_head.0 += delta.0;
-        offset_to_head.1 += delta.1;
+        offset_to_head += delta;
 
         // If the new offset has a _single_ component diff with abs > 2, we need
         // to snap to the direction dominated by the 2.
-        offset_to_head = match offset_to_head {
-            (-1..=1, 2) => (0, 1),
-            (-1..=1, -2) => (0, -1),
-            (2, -1..=1) => (1, 0),
-            (-2, -1..=1) => (-1, 0),
-            (x, y) => (x.signum(), y.signum()),
+        offset_to_head = match (offset_to_head.x, offset_to_head.y) {
+            (-1..=1, 2) => Point2::new(0, 1),
+            (-1..=1, -2) => Point2::new(0, -1),
+            (2, -1..=1) => Point2::new(1, 0),
+            (-2, -1..=1) => Point2::new(-1, 0),
+            _ => offset_to_head.signum(),
         };
 
-        let new_loc = (head.0 - offset_to_head.0, head.1 - offset_to_head.1);
-        let tail_delta = (new_loc.0 - curr.0, new_loc.1 - curr.1);
+        let new_loc = head - offset_to_head;
+        let tail_delta = new_loc - curr;
 
-        canvas.borrow_mut().move_entry(id, curr, new_loc);
+        canvas
+            .borrow_mut()
+            .move_entry(id, curr.into(), new_loc.into());
 
         curr = new_loc;
         (tail_delta, new_loc)
@@ -207,12 +215,9 @@ where
         .map(evaluate_moves('H', canvas.clone()))
         .map(follow_head('T', canvas.clone()))
         .map(|(_, p)| p)
-        .map(|v| {
-            canvas.borrow().render();
-            v
-        })
+        .inspect(|_| canvas.borrow().render())
         .unique()
-        .map(|coord| visited_canvas.move_entry('#', (0, 0), coord))
+        .map(|coord| visited_canvas.move_entry('#', (0, 0), coord.into()))
         .count();
 
     visited_canvas.render();
@@ -242,13 +247,10 @@ where
         .map(follow_head('7', canvas.clone())) // Knot 7
         .map(follow_head('8', canvas.clone())) // Knot 8
         .map(follow_head('9', canvas.clone())) // Knot 9
-        .map(|v| {
-            canvas.borrow().render();
-            v
-        })
+        .inspect(|_| canvas.borrow().render())
         .map(|(_, p)| p)
         .unique()
-        .map(|coord| visited_canvas.move_entry('#', (0, 0), coord))
+        .map(|coord| visited_canvas.move_entry('#', (0, 0), coord.into()))
         .count();
 
     visited_canvas.render();
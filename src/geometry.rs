@@ -0,0 +1,360 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use nom::{combinator::map, IResult};
+
+use crate::parsing::Parsable;
+
+/// A point (or an offset between points) on a 2d grid with y increasing
+/// downward
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Point2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A point (or offset) in 3d space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Component-wise arithmetic for point types
+macro_rules! point_ops {
+    ($point:ident { $($c:ident),+ }) => {
+        impl Add for $point {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self { $($c: self.$c + rhs.$c),+ }
+            }
+        }
+
+        impl Sub for $point {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self { $($c: self.$c - rhs.$c),+ }
+            }
+        }
+
+        impl Mul<i32> for $point {
+            type Output = Self;
+
+            fn mul(self, rhs: i32) -> Self {
+                Self { $($c: self.$c * rhs),+ }
+            }
+        }
+
+        impl Neg for $point {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($c: -self.$c),+ }
+            }
+        }
+
+        impl AddAssign for $point {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $point {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl $point {
+            /// Sum of the absolute differences along each axis
+            pub fn manhattan(&self, other: Self) -> i32 {
+                0 $(+ (self.$c - other.$c).abs())+
+            }
+
+            /// Largest absolute difference along any axis (how many king moves
+            /// apart the points are)
+            pub fn chebyshev(&self, other: Self) -> i32 {
+                0 $(.max((self.$c - other.$c).abs()))+
+            }
+
+            /// Unit step along each axis toward the sign of each component
+            pub fn signum(&self) -> Self {
+                Self { $($c: self.$c.signum()),+ }
+            }
+
+            /// Smallest of each component
+            pub fn min(&self, other: Self) -> Self {
+                Self { $($c: self.$c.min(other.$c)),+ }
+            }
+
+            /// Largest of each component
+            pub fn max(&self, other: Self) -> Self {
+                Self { $($c: self.$c.max(other.$c)),+ }
+            }
+
+            /// Whether the point lies on a face of the box with the given
+            /// corners
+            pub fn on_boundary(&self, min: Self, max: Self) -> bool {
+                false $(|| self.$c == min.$c || self.$c == max.$c)+
+            }
+        }
+    };
+}
+
+point_ops!(Point2 { x, y });
+point_ops!(Point3 { x, y, z });
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// The points sharing an edge with this one
+    pub fn neighbors4(self) -> impl Iterator<Item = Point2> {
+        Dir4::ALL.into_iter().map(move |d| d.apply(self))
+    }
+
+    /// The points sharing an edge or a corner with this one
+    pub fn neighbors8(self) -> impl Iterator<Item = Point2> {
+        Dir8::ALL.into_iter().map(move |d| d.apply(self))
+    }
+}
+
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3::new(0, 0, 0);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// The points sharing a face with this one
+    pub fn neighbors6(self) -> impl Iterator<Item = Point3> {
+        [
+            Point3::new(1, 0, 0),
+            Point3::new(-1, 0, 0),
+            Point3::new(0, 1, 0),
+            Point3::new(0, -1, 0),
+            Point3::new(0, 0, 1),
+            Point3::new(0, 0, -1),
+        ]
+        .into_iter()
+        .map(move |d| self + d)
+    }
+}
+
+impl From<(i32, i32)> for Point2 {
+    fn from((x, y): (i32, i32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Point2> for (i32, i32) {
+    fn from(p: Point2) -> Self {
+        (p.x, p.y)
+    }
+}
+
+impl From<(i32, i32, i32)> for Point3 {
+    fn from((x, y, z): (i32, i32, i32)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl Display for Point2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl Display for Point3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl Parsable for Point2 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        map(<(i32, i32)>::parse, Point2::from)(input)
+    }
+}
+
+impl Parsable for Point3 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        map(<(i32, i32, i32)>::parse, Point3::from)(input)
+    }
+}
+
+/// The four ways of facing on a grid. The discriminants are in clockwise
+/// order starting from east, which happens to be how day 22 scores facing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(usize)]
+pub enum Dir4 {
+    #[default]
+    E,
+    S,
+    W,
+    N,
+}
+
+impl Dir4 {
+    pub const ALL: [Dir4; 4] = [Dir4::E, Dir4::S, Dir4::W, Dir4::N];
+
+    /// Quarter turn counter-clockwise
+    pub fn left(&self) -> Self {
+        Self::ALL[(*self as usize + 3) % 4]
+    }
+
+    /// Quarter turn clockwise
+    pub fn right(&self) -> Self {
+        Self::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::ALL[(*self as usize + 2) % 4]
+    }
+
+    pub fn delta(&self) -> Point2 {
+        use Dir4::*;
+        match self {
+            E => Point2::new(1, 0),
+            S => Point2::new(0, 1),
+            W => Point2::new(-1, 0),
+            N => Point2::new(0, -1),
+        }
+    }
+
+    /// Get the point next to the given one in this direction
+    pub fn apply(&self, p: Point2) -> Point2 {
+        p + self.delta()
+    }
+
+    /// Read one of `>v<^`
+    pub fn from_arrow(c: char) -> Option<Self> {
+        use Dir4::*;
+        match c {
+            '>' => Some(E),
+            'v' => Some(S),
+            '<' => Some(W),
+            '^' => Some(N),
+            _ => None,
+        }
+    }
+
+    pub fn arrow(&self) -> char {
+        use Dir4::*;
+        match self {
+            E => '>',
+            S => 'v',
+            W => '<',
+            N => '^',
+        }
+    }
+}
+
+/// Compass directions including the diagonals, clockwise from north
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(usize)]
+pub enum Dir8 {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Dir8 {
+    pub const ALL: [Dir8; 8] = [
+        Dir8::N,
+        Dir8::NE,
+        Dir8::E,
+        Dir8::SE,
+        Dir8::S,
+        Dir8::SW,
+        Dir8::W,
+        Dir8::NW,
+    ];
+
+    /// Eighth of a turn counter-clockwise
+    pub fn left(&self) -> Self {
+        Self::ALL[(*self as usize + 7) % 8]
+    }
+
+    /// Eighth of a turn clockwise
+    pub fn right(&self) -> Self {
+        Self::ALL[(*self as usize + 1) % 8]
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::ALL[(*self as usize + 4) % 8]
+    }
+
+    pub fn delta(&self) -> Point2 {
+        use Dir8::*;
+        match self {
+            N => Point2::new(0, -1),
+            NE => Point2::new(1, -1),
+            E => Point2::new(1, 0),
+            SE => Point2::new(1, 1),
+            S => Point2::new(0, 1),
+            SW => Point2::new(-1, 1),
+            W => Point2::new(-1, 0),
+            NW => Point2::new(-1, -1),
+        }
+    }
+
+    pub fn apply(&self, p: Point2) -> Point2 {
+        p + self.delta()
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(d: Dir4) -> Self {
+        match d {
+            Dir4::E => Dir8::E,
+            Dir4::S => Dir8::S,
+            Dir4::W => Dir8::W,
+            Dir4::N => Dir8::N,
+        }
+    }
+}
+
+#[test]
+fn test_point_math() {
+    let a = Point2::new(1, -2);
+    let b = Point2::new(-3, 4);
+
+    assert_eq!(a + b, Point2::new(-2, 2));
+    assert_eq!((b - a) * 2, Point2::new(-8, 12));
+    assert_eq!(a.manhattan(b), 10);
+    assert_eq!(a.chebyshev(b), 6);
+    assert_eq!((b - a).signum(), Point2::new(-1, 1));
+    assert_eq!(Point2::ORIGIN.neighbors8().count(), 8);
+
+    let c = Point3::new(1, 2, 3);
+    assert_eq!(c.manhattan(Point3::ORIGIN), 6);
+    assert!(c.neighbors6().all(|n| n.manhattan(c) == 1));
+}
+
+#[test]
+fn test_directions() {
+    for d in Dir4::ALL {
+        assert_eq!(d.left().right(), d);
+        assert_eq!(d.right().right(), d.opposite());
+        assert_eq!(Dir8::from(d).right().right(), Dir8::from(d.right()));
+        assert_eq!(d.opposite().delta(), -d.delta());
+    }
+
+    for d in Dir8::ALL {
+        assert_eq!(d.opposite().delta(), -d.delta());
+    }
+}
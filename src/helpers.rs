@@ -1,8 +1,9 @@
 use std::sync::atomic::{AtomicBool, Ordering::*};
+use std::sync::OnceLock;
 use std::{
     fs::File,
     io::{BufRead, BufReader, Lines},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 static EXAMPLE: AtomicBool = AtomicBool::new(false);
@@ -15,6 +16,54 @@ pub fn set_example() {
     EXAMPLE.store(true, Relaxed);
 }
 
+static CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// Whether solvers should guard their integer math against overflow and
+/// inexact division (see `crate::checked`)
+pub fn is_checked() -> bool {
+    CHECKED.load(Relaxed)
+}
+
+pub fn set_checked() {
+    CHECKED.store(true, Relaxed);
+}
+
+static REPORT: AtomicBool = AtomicBool::new(false);
+
+/// Whether days with a fuller report than the answer should print it
+pub fn is_report() -> bool {
+    REPORT.load(Relaxed)
+}
+
+pub fn set_report() {
+    REPORT.store(true, Relaxed);
+}
+
+static RULES_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Rules spec to play day 2 by, if one was given on the command line
+pub fn rules_file() -> Option<&'static Path> {
+    RULES_FILE.get().map(PathBuf::as_path)
+}
+
+pub fn set_rules_file(path: PathBuf) {
+    RULES_FILE.set(path).expect("Rules file is only set once");
+}
+
+static REPLAY_STEPS: OnceLock<Vec<usize>> = OnceLock::new();
+
+/// Steps day 5 should draw the ship at, if a replay was asked for. Empty
+/// means every step
+pub fn replay_steps() -> Option<&'static [usize]> {
+    REPLAY_STEPS.get().map(Vec::as_slice)
+}
+
+pub fn set_replay_steps(steps: Vec<usize>) {
+    REPLAY_STEPS
+        .set(steps)
+        .expect("Replay steps are only set once");
+}
+
 /// This macro helps make defining and running the problems for each day simpler
 macro_rules! advent {
     ($(day $day_num:literal)+) => {
@@ -35,6 +84,31 @@ macro_rules! advent {
                 #[arg(short = 'x', long, default_value_t = false)]
                 example: bool,
 
+                /// Guard solver math against overflow and inexact division
+                #[arg(long, default_value_t = false)]
+                checked: bool,
+
+                /// Print fuller reports where days have them: day 1 ranks
+                /// every elf, day 2 compares the guide with the best plays,
+                /// day 3 lists every shared item, day 4 relates every pair
+                /// of assignments, day 6 lists every marker
+                #[arg(long, default_value_t = false)]
+                report: bool,
+
+                /// Rules spec for day 2 instead of rock paper scissors
+                #[arg(long, value_name = "FILE")]
+                rules: Option<std::path::PathBuf>,
+
+                /// Draw day 5's ship at these steps (0 is the start), or at
+                /// every step if none are listed
+                #[arg(
+                    long,
+                    value_name = "STEPS",
+                    value_delimiter = ',',
+                    num_args = 0..
+                )]
+                replay: Option<Vec<usize>>,
+
                 #[arg(long = "p1")]
                 problem_1: bool,
 
@@ -63,6 +137,22 @@ macro_rules! advent {
                 $crate::helpers::set_example();
             }
 
+            if args.checked {
+                $crate::helpers::set_checked();
+            }
+
+            if args.report {
+                $crate::helpers::set_report();
+            }
+
+            if let Some(rules) = args.rules {
+                $crate::helpers::set_rules_file(rules);
+            }
+
+            if let Some(steps) = args.replay {
+                $crate::helpers::set_replay_steps(steps);
+            }
+
             let run_all_problems = (!args.problem_1) && (!args.problem_2);
 
             paste! { $(
@@ -0,0 +1,497 @@
+use std::fmt::Display;
+
+/// Inclusive range of integers `start..=end`. Anything with `start > end` is
+/// empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn point(x: i64) -> Self {
+        Self::new(x, x)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    /// Number of integers in the interval, topping out at `i64::MAX`
+    pub fn len(&self) -> i64 {
+        self.end.saturating_sub(self.start).saturating_add(1).max(0)
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.start <= x && x <= self.end
+    }
+
+    /// True if every integer in the other interval is also in this one
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let result =
+            Interval::new(self.start.max(other.start), self.end.min(other.end));
+
+        (!result.is_empty()).then_some(result)
+    }
+
+    /// Where this interval sits relative to another one. Both need to be
+    /// non-empty. Sections are whole units, so `1-3` meets `4-6`
+    pub fn relation(&self, other: &Interval) -> Relation {
+        use std::cmp::Ordering::*;
+        use Relation::*;
+
+        // Widened so intervals at the ends of the range can't overflow
+        let gap_after = other.start as i128 - self.end as i128;
+        let gap_before = self.start as i128 - other.end as i128;
+
+        if gap_after > 1 {
+            return Before;
+        } else if gap_after == 1 {
+            return Meets;
+        } else if gap_before > 1 {
+            return After;
+        } else if gap_before == 1 {
+            return MetBy;
+        }
+
+        match (self.start.cmp(&other.start), self.end.cmp(&other.end)) {
+            (Equal, Equal) => Equals,
+            (Equal, Less) => Starts,
+            (Equal, Greater) => StartedBy,
+            (Greater, Equal) => Finishes,
+            (Less, Equal) => FinishedBy,
+            (Greater, Less) => During,
+            (Less, Greater) => Contains,
+            (Less, Less) => Overlaps,
+            (Greater, Greater) => OverlappedBy,
+        }
+    }
+}
+
+/// Allen's thirteen ways two intervals can be arranged, read as "this
+/// interval _relation_ the other one"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    /// Relation with the intervals swapped
+    pub fn inverse(&self) -> Self {
+        use Relation::*;
+
+        match self {
+            Before => After,
+            Meets => MetBy,
+            Overlaps => OverlappedBy,
+            Starts => StartedBy,
+            During => Contains,
+            Finishes => FinishedBy,
+            Equals => Equals,
+            FinishedBy => Finishes,
+            Contains => During,
+            StartedBy => Starts,
+            OverlappedBy => Overlaps,
+            MetBy => Meets,
+            After => Before,
+        }
+    }
+
+    /// True if the intervals share at least one integer
+    pub fn is_overlapping(&self) -> bool {
+        use Relation::*;
+
+        !matches!(self, Before | Meets | MetBy | After)
+    }
+
+    /// True if one of the intervals holds all of the other
+    pub fn is_containment(&self) -> bool {
+        use Relation::*;
+
+        matches!(
+            self,
+            Starts
+                | During
+                | Finishes
+                | Equals
+                | FinishedBy
+                | Contains
+                | StartedBy
+        )
+    }
+}
+
+impl From<(i64, i64)> for Interval {
+    fn from((start, end): (i64, i64)) -> Self {
+        Self::new(start, end)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Set of integers stored as sorted, disjoint intervals. Touching intervals
+/// are merged, so there is only ever one way to store a given set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add every integer in the interval to the set
+    pub fn insert(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        // Everything between lo and hi overlaps or touches the new interval
+        let lo = self
+            .intervals
+            .partition_point(|i| i.end < interval.start.saturating_sub(1));
+        let hi = self
+            .intervals
+            .partition_point(|i| i.start <= interval.end.saturating_add(1));
+
+        let mut merged = interval;
+        if lo < hi {
+            merged.start = merged.start.min(self.intervals[lo].start);
+            merged.end = merged.end.max(self.intervals[hi - 1].end);
+        }
+
+        self.intervals.splice(lo..hi, [merged]);
+    }
+
+    /// Take every integer in the interval out of the set
+    pub fn remove(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        let lo = self.intervals.partition_point(|i| i.end < interval.start);
+        let hi = self.intervals.partition_point(|i| i.start <= interval.end);
+
+        if lo == hi {
+            return;
+        }
+
+        let first = self.intervals[lo];
+        let last = self.intervals[hi - 1];
+
+        // Nothing is left over past the ends of the range
+        let leftovers = [
+            interval
+                .start
+                .checked_sub(1)
+                .map(|end| Interval::new(first.start, end)),
+            interval
+                .end
+                .checked_add(1)
+                .map(|start| Interval::new(start, last.end)),
+        ];
+
+        self.intervals.splice(
+            lo..hi,
+            leftovers.into_iter().flatten().filter(|i| !i.is_empty()),
+        );
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        other.iter().for_each(|i| result.insert(*i));
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let (a, b) = (&self.intervals, &other.intervals);
+        let (mut i, mut j) = (0, 0);
+        let mut intervals = vec![];
+
+        while i < a.len() && j < b.len() {
+            if let Some(both) = a[i].intersection(&b[j]) {
+                intervals.push(both);
+            }
+
+            if a[i].end < b[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        other.iter().for_each(|i| result.remove(*i));
+        result
+    }
+
+    /// Total number of integers in the set
+    pub fn len(&self) -> i64 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.contains_interval(&Interval::point(x))
+    }
+
+    /// True if every integer in the interval is in the set
+    pub fn contains_interval(&self, interval: &Interval) -> bool {
+        if interval.is_empty() {
+            return true;
+        }
+
+        let i = self.intervals.partition_point(|i| i.end < interval.start);
+
+        self.intervals
+            .get(i)
+            .map(|i| i.contains_interval(interval))
+            .unwrap_or(false)
+    }
+
+    /// Runs of integers within `bounds` that are not in the set
+    pub fn gaps(&self, bounds: Interval) -> impl Iterator<Item = Interval> {
+        IntervalSet::from_iter([bounds])
+            .difference(self)
+            .intervals
+            .into_iter()
+    }
+
+    /// The disjoint intervals making up the set in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        let mut result = IntervalSet::new();
+        iter.into_iter().for_each(|i| result.insert(i));
+        result
+    }
+}
+
+/// Intervals tagged with values that can be searched for everything
+/// overlapping a given interval. The intervals are sorted by start and sit
+/// under a tree holding the latest end in each subtree, so whole subtrees that
+/// end too early get skipped
+#[derive(Debug, Clone)]
+pub struct IntervalIndex<T> {
+    entries: Vec<(Interval, T)>,
+    max_end: Vec<i64>,
+}
+
+impl<T> IntervalIndex<T> {
+    fn build(&mut self, node: usize, lo: usize, hi: usize) -> i64 {
+        self.max_end[node] = if hi - lo == 1 {
+            self.entries[lo].0.end
+        } else {
+            let mid = (lo + hi) / 2;
+            self.build(2 * node, lo, mid)
+                .max(self.build(2 * node + 1, mid, hi))
+        };
+
+        self.max_end[node]
+    }
+
+    fn search<'a>(
+        &'a self,
+        node: usize,
+        (lo, hi): (usize, usize),
+        (query, candidates): (&Interval, usize),
+        found: &mut Vec<&'a (Interval, T)>,
+    ) {
+        if lo >= candidates || self.max_end[node] < query.start {
+            return;
+        }
+
+        if hi - lo == 1 {
+            found.push(&self.entries[lo]);
+            return;
+        }
+
+        let mid = (lo + hi) / 2;
+        self.search(2 * node, (lo, mid), (query, candidates), found);
+        self.search(2 * node + 1, (mid, hi), (query, candidates), found);
+    }
+
+    /// Every entry sharing at least one integer with the query, ordered by
+    /// start
+    pub fn overlapping(&self, query: &Interval) -> Vec<&(Interval, T)> {
+        let mut found = vec![];
+
+        // Nothing starting after the query ends can overlap it
+        let candidates =
+            self.entries.partition_point(|(i, _)| i.start <= query.end);
+
+        if !query.is_empty() {
+            let all = (0, self.entries.len());
+            self.search(1, all, (query, candidates), &mut found);
+        }
+
+        found
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T> FromIterator<(Interval, T)> for IntervalIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (Interval, T)>>(iter: I) -> Self {
+        let mut entries = iter
+            .into_iter()
+            .filter(|(i, _)| !i.is_empty())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(i, _)| *i);
+
+        let mut result = Self {
+            max_end: vec![i64::MIN; 4 * entries.len()],
+            entries,
+        };
+
+        if !result.entries.is_empty() {
+            result.build(1, 0, result.entries.len());
+        }
+
+        result
+    }
+}
+
+#[test]
+fn test_insert_merges() {
+    let set = IntervalSet::from_iter(
+        [(5, 7), (1, 2), (10, 12), (3, 3), (6, 11)].map(Interval::from),
+    );
+
+    assert_eq!(
+        set.iter().copied().collect::<Vec<_>>(),
+        vec![Interval::new(1, 3), Interval::new(5, 12)]
+    );
+    assert_eq!(set.len(), 11);
+    assert!(set.contains(8) && !set.contains(4));
+    assert!(set.contains_interval(&Interval::new(6, 12)));
+    assert!(!set.contains_interval(&Interval::new(2, 6)));
+}
+
+#[test]
+fn test_set_algebra() {
+    let a = IntervalSet::from_iter([(0, 10), (20, 30)].map(Interval::from));
+    let b = IntervalSet::from_iter([(5, 25)].map(Interval::from));
+
+    let pieces = |set: IntervalSet| {
+        set.iter().map(|i| (i.start, i.end)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(pieces(a.union(&b)), vec![(0, 30)]);
+    assert_eq!(pieces(a.intersection(&b)), vec![(5, 10), (20, 25)]);
+    assert_eq!(pieces(a.difference(&b)), vec![(0, 4), (26, 30)]);
+    assert_eq!(pieces(b.difference(&a)), vec![(11, 19)]);
+    assert_eq!(
+        a.gaps(Interval::new(-2, 35)).collect::<Vec<_>>(),
+        [(-2, -1), (11, 19), (31, 35)].map(Interval::from)
+    );
+}
+
+#[test]
+fn test_ends_of_the_range() {
+    use Relation::*;
+
+    let (min, max) = (i64::MIN, i64::MAX);
+    let everything = Interval::new(min, max);
+
+    assert_eq!(everything.len(), max);
+    assert_eq!(Interval::new(max, max).relation(&everything), Finishes);
+    assert_eq!(Interval::new(min, min).relation(&everything), Starts);
+    assert_eq!(
+        Interval::new(min, 0).relation(&Interval::new(1, max)),
+        Meets
+    );
+    assert_eq!(
+        Interval::new(0, max).relation(&Interval::new(min, -2)),
+        After
+    );
+
+    let mut set = IntervalSet::from_iter([everything]);
+    set.remove(Interval::new(min, -1));
+    assert_eq!(set.iter().collect::<Vec<_>>(), [&Interval::new(0, max)]);
+    set.remove(Interval::new(max - 1, max));
+    assert_eq!(set.iter().collect::<Vec<_>>(), [&Interval::new(0, max - 2)]);
+    set.remove(everything);
+    assert!(set.is_empty());
+}
+
+#[test]
+fn test_relations_and_index() {
+    use Relation::*;
+
+    let i = |s, e| Interval::new(s, e);
+
+    assert_eq!(i(1, 3).relation(&i(4, 6)), Meets);
+    assert_eq!(i(1, 3).relation(&i(5, 6)), Before);
+    assert_eq!(i(1, 4).relation(&i(4, 6)), Overlaps);
+    assert_eq!(i(4, 5).relation(&i(4, 6)), Starts);
+    assert_eq!(i(5, 5).relation(&i(4, 6)), During);
+    assert_eq!(i(2, 8).relation(&i(3, 7)), Contains);
+    assert_eq!(i(3, 7).relation(&i(2, 8)).inverse(), Contains);
+    assert!(Finishes.is_containment() && !Overlaps.is_containment());
+
+    let index = [(1, 3), (2, 9), (5, 6), (8, 8), (12, 20)]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (s, e))| (i(s, e), id))
+        .collect::<IntervalIndex<_>>();
+
+    let ids = |query| {
+        index
+            .overlapping(&query)
+            .into_iter()
+            .map(|(_, id)| *id)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ids(i(7, 8)), vec![1, 3]);
+    assert_eq!(ids(i(3, 5)), vec![0, 1, 2]);
+    assert_eq!(ids(i(10, 11)), vec![]);
+    assert_eq!(ids(i(0, 100)).len(), 5);
+}
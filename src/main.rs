@@ -2,8 +2,18 @@
 #![allow(unused_variables)]
 #![warn(rust_2018_idioms, missing_debug_implementations, clippy::all)]
 
+mod bitset;
 mod canvas;
+mod checked;
+mod components;
+mod cycle;
+mod geometry;
 mod helpers;
+mod interval;
+mod memo;
+mod parsing;
+mod search;
+mod topology;
 
 use helpers::advent;
 
@@ -0,0 +1,137 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// How well a [`Memo`] has been doing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+impl MemoStats {
+    /// Fraction of lookups answered from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Cache of answers to a recursive function keyed by the state it was called
+/// with. The function is handed a `recurse` callback to use instead of calling
+/// itself directly, so every sub-problem goes through the cache
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    capacity: Option<usize>,
+
+    /// Keys in the order they were cached, only tracked when bounded
+    order: VecDeque<K>,
+
+    pub stats: MemoStats,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            capacity: None,
+            order: VecDeque::new(),
+            stats: MemoStats::default(),
+        }
+    }
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache that holds on to at most `capacity` answers, forgetting the
+    /// oldest ones first
+    pub fn bounded(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.order.clear();
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if let Some(capacity) = self.capacity {
+            while self.cache.len() >= capacity.max(1) {
+                let Some(oldest) = self.order.pop_front() else {
+                    break;
+                };
+                self.cache.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+            self.order.push_back(key.clone());
+        }
+
+        self.cache.insert(key, value);
+    }
+
+    /// Get the answer for the given state, working it out with `f` if it
+    /// isn't cached yet
+    pub fn call<F>(&mut self, key: K, f: &F) -> V
+    where
+        F: Fn(&K, &mut dyn FnMut(K) -> V) -> V,
+    {
+        if let Some(value) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+
+        self.stats.misses += 1;
+
+        let value = f(&key, &mut |sub_key| self.call(sub_key, f));
+        self.insert(key, value.clone());
+
+        value
+    }
+}
+
+#[test]
+fn test_memo_fibonacci() {
+    let fib = |n: &u64, recurse: &mut dyn FnMut(u64) -> u64| {
+        if *n < 2 {
+            *n
+        } else {
+            recurse(n - 1) + recurse(n - 2)
+        }
+    };
+
+    let mut memo = Memo::new();
+    assert_eq!(memo.call(90, &fib), 2_880_067_194_370_816_120);
+    assert_eq!(memo.stats.misses, 91);
+    assert_eq!(memo.stats.hits, 88);
+
+    // Tiny caches still get the right answer, just with more work
+    let mut memo = Memo::bounded(3);
+    assert_eq!(memo.call(20, &fib), 6765);
+    assert!(memo.len() <= 3);
+    assert!(memo.stats.evictions > 0);
+}
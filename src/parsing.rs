@@ -0,0 +1,278 @@
+use std::fmt::Display;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, i32, i64, u32, u64},
+    combinator::{all_consuming, map},
+    error::Error,
+    sequence::{preceded, separated_pair, tuple},
+    Finish, IResult,
+};
+
+use crate::geometry::Point2;
+
+/// Match `$before`, then `$inner`, then `$after`, keeping only what `$inner`
+/// found
+macro_rules! between {
+    ($before:expr, $inner:expr, $after:expr) => {
+        nom::sequence::preceded(
+            $before,
+            nom::sequence::terminated($inner, $after),
+        )
+    };
+}
+
+pub(crate) use between;
+
+/// Things that know how to parse themselves out of puzzle input. Tuples are
+/// comma separated, so `(i32, i32, i32)` reads lines like `1,-2,3`
+pub trait Parsable: Sized {
+    fn parse(input: &str) -> IResult<&str, Self>;
+}
+
+impl Parsable for i32 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        i32(input)
+    }
+}
+
+impl Parsable for i64 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        i64(input)
+    }
+}
+
+impl Parsable for u32 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        u32(input)
+    }
+}
+
+impl Parsable for u64 {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        u64(input)
+    }
+}
+
+impl Parsable for usize {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        map(u64, |v| v as usize)(input)
+    }
+}
+
+impl<A: Parsable, B: Parsable> Parsable for (A, B) {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        separated_pair(A::parse, char(','), B::parse)(input)
+    }
+}
+
+impl<A: Parsable, B: Parsable, C: Parsable> Parsable for (A, B, C) {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        tuple((
+            A::parse,
+            preceded(char(','), B::parse),
+            preceded(char(','), C::parse),
+        ))(input)
+    }
+}
+
+/// Two values with the given separator between them, like `3,4` or the
+/// `3, y=4` part of `x=3, y=4`
+pub fn pair<'a, T: Parsable>(
+    sep: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (T, T)> {
+    separated_pair(T::parse, tag(sep), T::parse)
+}
+
+/// Same as [`pair`] with three values
+pub fn triple<'a, T: Parsable>(
+    sep: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (T, T, T)> {
+    tuple((
+        T::parse,
+        preceded(tag(sep), T::parse),
+        preceded(tag(sep), T::parse),
+    ))
+}
+
+/// A line of input that didn't parse
+#[derive(Debug)]
+pub struct LineError {
+    /// 1-based line number
+    pub line: usize,
+
+    /// 1-based column the parser gave up at
+    pub column: usize,
+
+    pub text: String,
+    pub reason: String,
+}
+
+impl Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {} in {:?}",
+            self.line, self.column, self.reason, self.text
+        )
+    }
+}
+
+impl std::error::Error for LineError {}
+
+/// Parse a whole line with the given parser. `number` is the 0-based position
+/// of the line in the input, for when lines can't all go through
+/// [`ParseLines`]
+pub fn parse_line<T, P>(
+    number: usize,
+    text: String,
+    parser: P,
+) -> Result<T, LineError>
+where
+    P: FnMut(&str) -> IResult<&str, T>,
+{
+    let result = all_consuming(parser)(&text)
+        .finish()
+        .map(|(_, value)| value)
+        .map_err(|Error { input, code }| (text.len() - input.len(), code));
+
+    result.map_err(|(offset, code)| LineError {
+        line: number + 1,
+        column: offset + 1,
+        reason: format!("{code:?}"),
+        text,
+    })
+}
+
+/// Parse every line of input in one go, keeping track of line numbers so
+/// errors can say where they happened. Every line has to be parsed completely
+pub trait ParseLines: Iterator<Item = String> + Sized {
+    /// Every line is a single [`Parsable`] value
+    fn parse_lines<T: Parsable>(
+        self,
+    ) -> impl Iterator<Item = Result<T, LineError>> {
+        self.parse_lines_with(T::parse)
+    }
+
+    /// Every line is parsed by the given parser
+    fn parse_lines_with<T, P>(
+        self,
+        mut parser: P,
+    ) -> impl Iterator<Item = Result<T, LineError>>
+    where
+        P: FnMut(&str) -> IResult<&str, T>,
+    {
+        self.enumerate()
+            .map(move |(number, text)| parse_line(number, text, &mut parser))
+    }
+}
+
+impl<I: Iterator<Item = String>> ParseLines for I {}
+
+/// Group lines into the blocks separated by blank lines. The blank lines are
+/// dropped
+pub fn blocks<I>(lines: I) -> impl Iterator<Item = Vec<String>>
+where
+    I: Iterator<Item = String>,
+{
+    let mut block = vec![];
+
+    lines
+        .map(Some)
+        .chain(Some(None)) // Finish the last block
+        .filter_map(move |line| match line {
+            Some(line) if !line.is_empty() => {
+                block.push(line);
+                None
+            }
+            _ => (!block.is_empty()).then(|| std::mem::take(&mut block)),
+        })
+}
+
+/// Rectangle of characters as drawn in the input. Short lines are padded out
+/// to the width of the longest one
+#[derive(Debug, Clone)]
+pub struct CharGrid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<char>,
+}
+
+impl CharGrid {
+    pub fn from_lines<I, S>(lines: I, fill: char) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let rows = lines
+            .into_iter()
+            .map(|line| line.as_ref().chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+
+        let cells = rows
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(width, fill);
+                row
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn get(&self, Point2 { x, y }: Point2) -> Option<char> {
+        let in_bounds = x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height;
+
+        in_bounds.then(|| self.cells[y as usize * self.width + x as usize])
+    }
+
+    pub fn row(&self, y: usize) -> &[char] {
+        &self.cells[(y * self.width)..((y + 1) * self.width)]
+    }
+
+    /// Every cell with its coordinate in reading order
+    pub fn iter(&self) -> impl Iterator<Item = (Point2, char)> + '_ {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(i, c)| {
+            (Point2::new((i % width) as i32, (i / width) as i32), *c)
+        })
+    }
+}
+
+#[test]
+fn test_parse_lines_reports_position() {
+    let lines = ["1,2,3", "4,5,x", "-6,7,8"].map(String::from);
+    let parsed = lines
+        .into_iter()
+        .parse_lines::<(i32, i32, i32)>()
+        .collect::<Vec<_>>();
+
+    assert_eq!(parsed[0].as_ref().ok(), Some(&(1, 2, 3)));
+    assert_eq!(parsed[2].as_ref().ok(), Some(&(-6, 7, 8)));
+
+    let error = parsed[1].as_ref().expect_err("Third value isn't a number");
+    assert_eq!((error.line, error.column), (2, 5));
+}
+
+#[test]
+fn test_blocks_and_grid() {
+    let lines = ["", "ab", "c", "", "", "d"].map(String::from);
+    let groups = blocks(lines.into_iter()).collect::<Vec<_>>();
+    assert_eq!(groups, vec![vec!["ab", "c"], vec!["d"]]);
+
+    let grid = CharGrid::from_lines(&groups[0], '.');
+    assert_eq!((grid.width, grid.height), (2, 2));
+    assert_eq!(grid.row(1), &['c', '.']);
+    assert_eq!(grid.get(Point2::new(1, 0)), Some('b'));
+    assert_eq!(grid.get(Point2::new(2, 0)), None);
+}
@@ -0,0 +1,395 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use priority_queue::PriorityQueue;
+
+/// Distances (and weights) in searches
+pub type Cost = i32;
+
+/// Anything that can list the nodes reachable in one step from a node. Plain
+/// closures of the form `|node: &N| -> impl IntoIterator<Item = N>` work
+pub trait Neighbors<N> {
+    type Iter: IntoIterator<Item = N>;
+
+    fn neighbors(&self, node: &N) -> Self::Iter;
+}
+
+impl<N, F, I> Neighbors<N> for F
+where
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    type Iter = I;
+
+    fn neighbors(&self, node: &N) -> Self::Iter {
+        self(node)
+    }
+}
+
+/// Same as [`Neighbors`] but each step comes with a cost
+pub trait WeightedNeighbors<N> {
+    type Iter: IntoIterator<Item = (N, Cost)>;
+
+    fn neighbors(&self, node: &N) -> Self::Iter;
+}
+
+impl<N, F, I> WeightedNeighbors<N> for F
+where
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = (N, Cost)>,
+{
+    type Iter = I;
+
+    fn neighbors(&self, node: &N) -> Self::Iter {
+        self(node)
+    }
+}
+
+/// Everything learned by a single-source (or multi-source) search
+#[derive(Debug)]
+pub struct SearchResult<N> {
+    /// Shortest known distance to every node reached
+    pub distances: HashMap<N, Cost>,
+
+    /// The goal node the search stopped at, if it found one
+    pub goal: Option<N>,
+
+    parents: HashMap<N, N>,
+}
+
+impl<N> SearchResult<N>
+where
+    N: Clone + Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            distances: HashMap::new(),
+            goal: None,
+            parents: HashMap::new(),
+        }
+    }
+
+    pub fn distance(&self, node: &N) -> Option<Cost> {
+        self.distances.get(node).copied()
+    }
+
+    /// Distance to the goal the search stopped at
+    pub fn goal_distance(&self) -> Option<Cost> {
+        self.goal.as_ref().and_then(|goal| self.distance(goal))
+    }
+
+    /// Walk back from the given node to the start of the search. The result
+    /// includes both ends
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        self.distances.get(node)?;
+
+        let mut path = vec![node.clone()];
+        while let Some(parent) = self.parents.get(path.last()?) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Path from the start of the search to the goal it stopped at
+    pub fn goal_path(&self) -> Option<Vec<N>> {
+        self.goal.as_ref().and_then(|goal| self.path_to(goal))
+    }
+}
+
+/// Breadth first search where every step costs 1. Stops at the first node
+/// that satisfies `is_goal` (pass `|_| false` to explore everything)
+pub fn bfs<N, G, F>(start: N, graph: &G, is_goal: F) -> SearchResult<N>
+where
+    N: Clone + Eq + Hash,
+    G: Neighbors<N>,
+    F: Fn(&N) -> bool,
+{
+    multi_bfs([start], graph, is_goal)
+}
+
+/// Breadth first search starting from all the given nodes at once
+pub fn multi_bfs<N, S, G, F>(
+    starts: S,
+    graph: &G,
+    is_goal: F,
+) -> SearchResult<N>
+where
+    N: Clone + Eq + Hash,
+    S: IntoIterator<Item = N>,
+    G: Neighbors<N>,
+    F: Fn(&N) -> bool,
+{
+    let mut result = SearchResult::new();
+    let mut queue = VecDeque::new();
+
+    for start in starts {
+        if !result.distances.contains_key(&start) {
+            result.distances.insert(start.clone(), 0);
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        if is_goal(&node) {
+            result.goal = Some(node);
+            break;
+        }
+
+        let dist = result.distances[&node];
+
+        for next in graph.neighbors(&node) {
+            if !result.distances.contains_key(&next) {
+                result.distances.insert(next.clone(), dist + 1);
+                result.parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+
+    result
+}
+
+/// Shortest paths with non-negative step costs
+pub fn dijkstra<N, G, F>(start: N, graph: &G, is_goal: F) -> SearchResult<N>
+where
+    N: Clone + Eq + Hash,
+    G: WeightedNeighbors<N>,
+    F: Fn(&N) -> bool,
+{
+    astar(start, graph, is_goal, |_| 0)
+}
+
+/// Dijkstra guided by a heuristic. The heuristic must never overestimate the
+/// remaining cost to a goal (and shouldn't drop by more than a step's cost
+/// between neighbors), or the distances found may not be the shortest
+pub fn astar<N, G, F, H>(
+    start: N,
+    graph: &G,
+    is_goal: F,
+    heuristic: H,
+) -> SearchResult<N>
+where
+    N: Clone + Eq + Hash,
+    G: WeightedNeighbors<N>,
+    F: Fn(&N) -> bool,
+    H: Fn(&N) -> Cost,
+{
+    let mut result = SearchResult::new();
+    let mut queue = PriorityQueue::new();
+
+    result.distances.insert(start.clone(), 0);
+    queue.push(start.clone(), Reverse(heuristic(&start)));
+
+    while let Some((node, _)) = queue.pop() {
+        if is_goal(&node) {
+            result.goal = Some(node);
+            break;
+        }
+
+        let dist = result.distances[&node];
+
+        for (next, cost) in graph.neighbors(&node) {
+            let next_dist = dist + cost;
+
+            if result
+                .distances
+                .get(&next)
+                .map(|known| next_dist < *known)
+                .unwrap_or(true)
+            {
+                result.distances.insert(next.clone(), next_dist);
+                result.parents.insert(next.clone(), node.clone());
+                let priority = Reverse(next_dist + heuristic(&next));
+                queue.push_increase(next, priority);
+            }
+        }
+    }
+
+    result
+}
+
+/// What a depth first search saw
+#[derive(Debug)]
+pub struct DepthFirst<N> {
+    /// Nodes in the order the search finished with them (post-order)
+    pub finished: Vec<N>,
+
+    /// The goal node the search stopped at, if it found one
+    pub goal: Option<N>,
+}
+
+/// Depth first search that visits neighbors in the order given. Stops as soon
+/// as a node satisfying `is_goal` is reached
+pub fn dfs<N, G, F>(start: N, graph: &G, is_goal: F) -> DepthFirst<N>
+where
+    N: Clone + Eq + Hash,
+    G: Neighbors<N>,
+    F: Fn(&N) -> bool,
+{
+    let mut visited = HashSet::new();
+    let mut finished = vec![];
+    let mut stack = vec![];
+
+    visited.insert(start.clone());
+    stack.push((start.clone(), graph.neighbors(&start).into_iter()));
+
+    while let Some((node, children)) = stack.last_mut() {
+        if is_goal(node) {
+            return DepthFirst {
+                finished,
+                goal: Some(node.clone()),
+            };
+        }
+
+        match children.find(|child| !visited.contains(child)) {
+            Some(child) => {
+                visited.insert(child.clone());
+                let grandchildren = graph.neighbors(&child).into_iter();
+                stack.push((child, grandchildren));
+            }
+            None => {
+                finished.push(node.clone());
+                stack.pop();
+            }
+        }
+    }
+
+    DepthFirst {
+        finished,
+        goal: None,
+    }
+}
+
+/// Shortest distances between every pair of nodes in a graph whose nodes are
+/// numbered `0..len`
+#[derive(Debug)]
+pub struct AllPairs {
+    len: usize,
+    dist: Vec<Option<Cost>>,
+    next: Vec<Option<usize>>,
+}
+
+impl AllPairs {
+    pub fn distance(&self, from: usize, to: usize) -> Option<Cost> {
+        self.dist[from * self.len + to]
+    }
+
+    /// Shortest path between the two nodes including both ends
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        self.distance(from, to)?;
+
+        let mut path = vec![from];
+        let mut curr = from;
+        while curr != to {
+            curr = self.next[curr * self.len + to]?;
+            path.push(curr);
+        }
+
+        Some(path)
+    }
+}
+
+/// Floyd–Warshall over the nodes `0..len`
+pub fn floyd_warshall<G>(len: usize, graph: &G) -> AllPairs
+where
+    G: WeightedNeighbors<usize>,
+{
+    let mut dist = vec![None; len * len];
+    let mut next = vec![None; len * len];
+
+    for i in 0..len {
+        dist[i * len + i] = Some(0);
+        next[i * len + i] = Some(i);
+
+        for (j, cost) in graph.neighbors(&i) {
+            if dist[i * len + j].map(|d| cost < d).unwrap_or(true) {
+                dist[i * len + j] = Some(cost);
+                next[i * len + j] = Some(j);
+            }
+        }
+    }
+
+    for k in 0..len {
+        for i in 0..len {
+            for j in 0..len {
+                if let (Some(ik), Some(kj)) =
+                    (dist[i * len + k], dist[k * len + j])
+                {
+                    if dist[i * len + j].map(|d| ik + kj < d).unwrap_or(true) {
+                        dist[i * len + j] = Some(ik + kj);
+                        next[i * len + j] = next[i * len + k];
+                    }
+                }
+            }
+        }
+    }
+
+    AllPairs { len, dist, next }
+}
+
+#[test]
+fn test_bfs_and_path() {
+    // 0 - 1 - 2 - 3
+    //  \_____4___/
+    let edges = [vec![1, 4], vec![0, 2], vec![1, 3], vec![2, 4], vec![0, 3]];
+    let graph = |n: &usize| edges[*n].clone();
+
+    let result = bfs(0, &graph, |n| *n == 3);
+    assert_eq!(result.goal_distance(), Some(2));
+    assert_eq!(result.goal_path(), Some(vec![0, 4, 3]));
+
+    let result = multi_bfs([1, 2], &graph, |_| false);
+    assert_eq!(result.distance(&4), Some(2));
+    assert_eq!(result.distances.len(), 5);
+}
+
+#[test]
+fn test_weighted_searches_agree() {
+    let graph = |(x, y): &(i32, i32)| {
+        [(1, 0), (0, 1), (-1, 0), (0, -1)]
+            .into_iter()
+            .map(move |(dx, dy)| (x + dx, y + dy))
+            .filter(|(x, y)| (0..5).contains(x) && (0..5).contains(y))
+            .map(|(x, y)| ((x, y), 1 + (x == 2 && y < 4) as Cost * 10))
+            .collect::<Vec<_>>()
+    };
+
+    let plain = dijkstra((0, 0), &graph, |p| *p == (4, 0));
+    let guided = astar(
+        (0, 0),
+        &graph,
+        |p| *p == (4, 0),
+        |(x, y)| (4 - x).abs() + y.abs(),
+    );
+
+    assert_eq!(plain.goal_distance(), Some(12));
+    assert_eq!(guided.goal_distance(), Some(12));
+    assert_eq!(guided.goal_path().map(|p| p.len()), Some(13));
+}
+
+#[test]
+fn test_dfs_post_order() {
+    let edges = [vec![1, 2], vec![3], vec![3], vec![]];
+    let graph = |n: &usize| edges[*n].clone();
+
+    let result = dfs(0, &graph, |_| false);
+    assert_eq!(result.finished, vec![3, 1, 2, 0]);
+
+    let result = dfs(0, &graph, |n| *n == 2);
+    assert_eq!((result.finished, result.goal), (vec![3, 1], Some(2)));
+}
+
+#[test]
+fn test_floyd_warshall() {
+    let edges = [vec![(1, 4), (2, 1)], vec![(3, 1)], vec![(1, 2)], vec![]];
+    let all_pairs = floyd_warshall(4, &|n: &usize| edges[*n].clone());
+
+    assert_eq!(all_pairs.distance(0, 3), Some(4));
+    assert_eq!(all_pairs.path(0, 3), Some(vec![0, 2, 1, 3]));
+    assert_eq!(all_pairs.distance(3, 0), None);
+}
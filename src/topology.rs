@@ -0,0 +1,382 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::geometry::{Dir4, Point2};
+
+/// A cell on a 2d grid with y increasing downward
+pub type Cell = Point2;
+
+/// The shape of the space a simulation runs in. Answers the question of where
+/// you end up (and which way you are facing) if you take a single step from a
+/// cell. `None` means the step falls off the edge of the world
+pub trait Topology {
+    fn step(&self, cell: Cell, heading: Dir4) -> Option<(Cell, Dir4)>;
+}
+
+/// Plain old rectangle with hard edges
+#[derive(Debug, Clone, Copy)]
+pub struct Bounded {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Bounded {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn contains(&self, Point2 { x, y }: Cell) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+}
+
+impl Topology for Bounded {
+    fn step(&self, cell: Cell, heading: Dir4) -> Option<(Cell, Dir4)> {
+        let next = heading.apply(cell);
+        self.contains(next).then_some((next, heading))
+    }
+}
+
+/// Rectangle where walking off one edge puts you back on the opposite edge
+#[derive(Debug, Clone, Copy)]
+pub struct Torus {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Torus {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+
+    /// Bring any cell, no matter how far outside the rectangle, back into it
+    pub fn wrap(&self, Point2 { x, y }: Cell) -> Cell {
+        Point2::new(x.rem_euclid(self.width), y.rem_euclid(self.height))
+    }
+}
+
+impl Topology for Torus {
+    fn step(&self, cell: Cell, heading: Dir4) -> Option<(Cell, Dir4)> {
+        Some((self.wrap(heading.apply(cell)), heading))
+    }
+}
+
+/// An irregular region where walking off the region wraps around to the other
+/// end of the same row or column (like day 22 part 1). Every row and column is
+/// expected to be one contiguous run of cells
+#[derive(Debug, Clone)]
+pub struct WrappedNet {
+    rows: Vec<(i32, i32)>,
+    cols: Vec<(i32, i32)>,
+}
+
+impl WrappedNet {
+    pub fn new<F>(width: i32, height: i32, in_net: F) -> Self
+    where
+        F: Fn(Cell) -> bool,
+    {
+        let mut rows = vec![(i32::MAX, i32::MIN); height as usize];
+        let mut cols = vec![(i32::MAX, i32::MIN); width as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                if in_net(Point2::new(x, y)) {
+                    let row = &mut rows[y as usize];
+                    let col = &mut cols[x as usize];
+                    *row = (row.0.min(x), row.1.max(x));
+                    *col = (col.0.min(y), col.1.max(y));
+                }
+            }
+        }
+
+        Self { rows, cols }
+    }
+
+    fn contains(&self, Point2 { x, y }: Cell) -> bool {
+        self.rows
+            .get(y as usize)
+            .filter(|_| y >= 0)
+            .map(|(min, max)| (*min..=*max).contains(&x))
+            .unwrap_or(false)
+    }
+}
+
+impl Topology for WrappedNet {
+    fn step(&self, cell: Cell, heading: Dir4) -> Option<(Cell, Dir4)> {
+        use Dir4::*;
+
+        let next = heading.apply(cell);
+
+        if self.contains(next) {
+            return Some((next, heading));
+        }
+
+        let Point2 { x, y } = cell;
+        let row = self.rows[y as usize];
+        let col = self.cols[x as usize];
+
+        let wrapped = match heading {
+            E => Point2::new(row.0, y),
+            W => Point2::new(row.1, y),
+            S => Point2::new(x, col.0),
+            N => Point2::new(x, col.1),
+        };
+
+        Some((wrapped, heading))
+    }
+}
+
+type Vec3 = [i32; 3];
+
+fn neg(v: Vec3) -> Vec3 {
+    [-v[0], -v[1], -v[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Orientation of a face of the cube in 3d space. `right` and `down` are the
+/// directions east and south on the net point once the face is folded up
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    normal: Vec3,
+    right: Vec3,
+    down: Vec3,
+}
+
+impl Frame {
+    fn axis(&self, heading: Dir4) -> Vec3 {
+        use Dir4::*;
+        match heading {
+            E => self.right,
+            S => self.down,
+            W => neg(self.right),
+            N => neg(self.down),
+        }
+    }
+
+    /// Get the frame of the face that sits next to this one on the net in the
+    /// given heading after folding along the shared edge
+    fn fold(&self, heading: Dir4) -> Self {
+        use Dir4::*;
+        let Frame {
+            normal,
+            right,
+            down,
+        } = *self;
+
+        match heading {
+            E => Frame {
+                normal: right,
+                right: neg(normal),
+                down,
+            },
+            W => Frame {
+                normal: neg(right),
+                right: normal,
+                down,
+            },
+            S => Frame {
+                normal: down,
+                right,
+                down: neg(normal),
+            },
+            N => Frame {
+                normal: neg(down),
+                right,
+                down: normal,
+            },
+        }
+    }
+}
+
+/// The surface of a cube described by a flat net of six square faces (day 22
+/// part 2). Faces are found by folding the net up, so any valid layout works
+#[derive(Debug, Clone)]
+pub struct CubeNet {
+    side: i32,
+    faces: HashMap<Cell, Frame>,
+}
+
+impl CubeNet {
+    pub fn new<F>(side: i32, width: i32, height: i32, in_net: F) -> Self
+    where
+        F: Fn(Cell) -> bool,
+    {
+        let blocks = (0..(height / side))
+            .flat_map(|by| {
+                (0..(width / side)).map(move |bx| Point2::new(bx, by))
+            })
+            .filter(|block| in_net(*block * side))
+            .collect::<Vec<_>>();
+
+        assert_eq!(blocks.len(), 6, "A cube net needs exactly 6 faces");
+
+        let mut faces = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        queue.push_back((
+            blocks[0],
+            Frame {
+                normal: [0, 0, -1],
+                right: [1, 0, 0],
+                down: [0, 1, 0],
+            },
+        ));
+
+        while let Some((block, frame)) = queue.pop_front() {
+            if faces.contains_key(&block) {
+                continue;
+            }
+            faces.insert(block, frame);
+
+            for heading in Dir4::ALL {
+                let next = heading.apply(block);
+                if blocks.contains(&next) && !faces.contains_key(&next) {
+                    queue.push_back((next, frame.fold(heading)));
+                }
+            }
+        }
+
+        assert_eq!(faces.len(), 6, "Cube net faces must all be connected");
+
+        Self { side, faces }
+    }
+
+    fn block_of(&self, Point2 { x, y }: Cell) -> Cell {
+        Point2::new(x.div_euclid(self.side), y.div_euclid(self.side))
+    }
+}
+
+impl Topology for CubeNet {
+    fn step(&self, cell: Cell, heading: Dir4) -> Option<(Cell, Dir4)> {
+        let next = heading.apply(cell);
+        let block = self.block_of(cell);
+        let frame = self.faces.get(&block)?;
+
+        if self.faces.contains_key(&self.block_of(next)) {
+            return Some((next, heading));
+        }
+
+        // Work in doubled coordinates so that cell centers are integers. The
+        // cube spans -side..=side on every axis, and stepping over an edge
+        // moves half a cell along the heading and half a cell into the cube
+        let s = self.side;
+        let Point2 { x: i, y: j } = cell - block * s;
+        let travel = frame.axis(heading);
+
+        let point: Vec3 = std::array::from_fn(|k| {
+            frame.normal[k] * s
+                + frame.right[k] * (2 * i + 1 - s)
+                + frame.down[k] * (2 * j + 1 - s)
+                + travel[k]
+                - frame.normal[k]
+        });
+
+        let (target_block, target) = self
+            .faces
+            .iter()
+            .find(|(_, f)| f.normal == travel)
+            .expect("Every direction has a face");
+
+        let new_i = (dot(point, target.right) + s - 1) / 2;
+        let new_j = (dot(point, target.down) + s - 1) / 2;
+
+        let inward = neg(frame.normal);
+        let new_heading = Dir4::ALL
+            .into_iter()
+            .find(|h| target.axis(*h) == inward)
+            .expect("Some heading must point away from the old face");
+
+        Some((*target_block * s + Point2::new(new_i, new_j), new_heading))
+    }
+}
+
+#[test]
+fn test_bounded_edges() {
+    let plane = Bounded::new(3, 2);
+    assert_eq!(plane.step(Point2::new(2, 0), Dir4::E), None);
+    assert_eq!(
+        plane.step(Point2::new(1, 0), Dir4::S),
+        Some((Point2::new(1, 1), Dir4::S))
+    );
+}
+
+#[test]
+fn test_torus_wrap() {
+    let torus = Torus::new(6, 4);
+    assert_eq!(
+        torus.step(Point2::new(5, 0), Dir4::E),
+        Some((Point2::new(0, 0), Dir4::E))
+    );
+    assert_eq!(
+        torus.step(Point2::new(2, 0), Dir4::N),
+        Some((Point2::new(2, 3), Dir4::N))
+    );
+    assert_eq!(torus.wrap(Point2::new(-13, 9)), Point2::new(5, 1));
+}
+
+#[test]
+fn test_wrapped_net() {
+    // Staircase shaped region
+    // ##..
+    // .###
+    let net =
+        WrappedNet::new(4, 2, |c| if c.y == 0 { c.x < 2 } else { c.x > 0 });
+    assert_eq!(
+        net.step(Point2::new(1, 0), Dir4::E),
+        Some((Point2::new(0, 0), Dir4::E))
+    );
+    assert_eq!(
+        net.step(Point2::new(1, 1), Dir4::W),
+        Some((Point2::new(3, 1), Dir4::W))
+    );
+    assert_eq!(
+        net.step(Point2::new(2, 1), Dir4::S),
+        Some((Point2::new(2, 1), Dir4::S))
+    );
+}
+
+#[test]
+fn test_cube_net_round_trip() {
+    // The layouts from the day 22 example and from the real input
+    let layouts: [&[(i32, i32)]; 2] = [
+        &[(2, 0), (0, 1), (1, 1), (2, 1), (2, 2), (3, 2)],
+        &[(1, 0), (2, 0), (1, 1), (0, 2), (1, 2), (0, 3)],
+    ];
+
+    for layout in layouts {
+        let side = 4;
+        let net = CubeNet::new(side, 4 * side, 4 * side, |c| {
+            layout.contains(&(c.x / side, c.y / side))
+        });
+
+        // Walking off any edge and straight back should land where we started
+        for (bx, by) in layout {
+            for k in 0..side {
+                let corner = Point2::new(bx * side, by * side);
+                for (offset, heading) in [
+                    ((k, 0), Dir4::N),
+                    ((k, side - 1), Dir4::S),
+                    ((0, k), Dir4::W),
+                    ((side - 1, k), Dir4::E),
+                ] {
+                    let cell = corner + offset.into();
+                    let (out, out_heading) = net.step(cell, heading).unwrap();
+                    let back = net.step(out, out_heading.opposite());
+                    assert_eq!(back, Some((cell, heading.opposite())));
+                }
+            }
+        }
+    }
+
+    // Example: walking east off the right of face 4 lands on top of face 6
+    let side = 4;
+    let net = CubeNet::new(side, 16, 12, |c| {
+        layouts[0].contains(&(c.x / side, c.y / side))
+    });
+    assert_eq!(
+        net.step(Point2::new(11, 5), Dir4::E),
+        Some((Point2::new(14, 8), Dir4::S))
+    );
+}